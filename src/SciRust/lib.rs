@@ -1,10 +1,11 @@
 #![crate_id = "SciRust#0.3pre"]
 #![comment = "A Scientific Computing Library for Rust"]
 #![crate_type = "lib"]
-#![feature(lang_items)]
+#![feature(lang_items, macro_rules)]
 
 extern crate rand;
 extern crate sync;
 extern crate debug;
 
 pub mod matrix;
+pub mod scalar;
@@ -1,32 +1,53 @@
 #![allow(uppercase_variables)]
 
+use std::cmp;
 use std::num;
 
-use matrix::{BasicMatrix, Create, SubMatrix, 
-             TransposeMatrix, Vector,
+use matrix::{BasicMatrix, Create, Matrix, SubMatrix,
+             Vector, Triangle, Lower, Upper,
+             Semiring, Ring, Field, RealField,
              col, row};
-use matrix::generate::zero_matrix;
-
-pub fn dot<T: num::Num, L: Vector<T>, R: Vector<T>>(lhs: &L, rhs: &R) -> T {
-    assert!(lhs.len() > 0)
+use matrix::generate::{identity, zero_matrix};
+use matrix::triangular;
+use matrix::triangular::LowerTriangular;
+use matrix::vector::DenseVector;
+use scalar::Conjugate;
+use scalar::complex::Complex;
+
+// The empty sum is zero, so empty vectors are fine here, as is mat_mul
+// with an inner dimension of zero.
+pub fn dot<T: Semiring, L: Vector<T>, R: Vector<T>>(lhs: &L, rhs: &R) -> T {
     if lhs.len() != rhs.len() {
         fail!("Invalid vector lengths.")
     }
 
     //error!("%? ### %?", lhs, rhs);
 
-    let a = lhs[0];
-    let b = rhs[0];
-
-    let mut acc : T = a * b;
-    for i in range(1, lhs.len()) {
+    let mut acc: T = Semiring::zero();
+    for i in range(0, lhs.len()) {
         acc = acc + (lhs[i] * rhs[i])
     }
 
     acc
 }
 
-pub fn mat_mul<T: Num + num::FromPrimitive, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Res: BasicMatrix<T> + Create<T>> (lhs: &LHS, rhs: &RHS) -> Res
+// The Hermitian inner product, conj(lhs) . rhs. For real element types
+// this is the same as dot. Note that mat_mul uses the unconjugated dot,
+// as a matrix product should.
+pub fn dotc<T: Semiring + Conjugate, L: Vector<T>, R: Vector<T>>(lhs: &L, rhs: &R) -> T {
+    if lhs.len() != rhs.len() {
+        fail!("Invalid vector lengths.")
+    }
+
+    let mut acc: T = Semiring::zero();
+    for i in range(0, lhs.len()) {
+        acc = acc + lhs[i].conj() * rhs[i]
+    }
+
+    acc
+}
+
+pub fn mat_mul<T: Semiring, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Res: BasicMatrix<T> + Create<T>> (lhs: &LHS, rhs: &RHS) -> Res
 {
     if lhs.num_cols() != rhs.num_rows() {
         fail!(format!("Incompatible matrix sizes. LHS: {:?}, RHS: {:?}",
@@ -34,12 +55,478 @@ pub fn mat_mul<T: Num + num::FromPrimitive, LHS: BasicMatrix<T>, RHS: BasicMatri
                    (rhs.num_rows(), rhs.num_cols())))
     }
 
+    if lhs.diagonal_blocks().is_some() || rhs.diagonal_blocks().is_some() {
+        return block_diagonal_mat_mul(lhs, rhs)
+    }
+
+    if lhs.triangle().is_some() || rhs.triangle().is_some() {
+        return triangular::mat_mul(lhs, rhs)
+    }
+
     Create::<T>::create(lhs.num_rows(), rhs.num_cols(),
                         |i, j| dot(&row(lhs, i), &col(rhs, j)))
 }
 
+// The diagonal block holding row or column i, where block k covers
+// offsets[k] to offsets[k + 1].
+pub fn block_of(offsets: &[uint], i: uint) -> uint {
+    let mut lo = 0;
+    let mut hi = offsets.len() - 1;
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if offsets[mid] <= i { lo = mid } else { hi = mid }
+    }
+    lo
+}
+
+// The indices of row or column i of an n x n matrix that can be
+// nonzero.
+fn block_range(blocks: &Option<Vec<uint>>, i: uint, n: uint) -> (uint, uint) {
+    match *blocks {
+        Some(ref offsets) => {
+            let k = block_of(offsets.as_slice(), i);
+            (*offsets.get(k), *offsets.get(k + 1))
+        }
+        None => (0, n)
+    }
+}
+
+// Matrix multiply where either side may be block diagonal. The inner
+// sum only runs over the block holding row i of lhs or column j of rhs.
+fn block_diagonal_mat_mul<T: Semiring, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Res: BasicMatrix<T> + Create<T>>
+(lhs: &LHS, rhs: &RHS) -> Res
+{
+    let n = lhs.num_cols();
+    let lb = lhs.diagonal_blocks();
+    let rb = rhs.diagonal_blocks();
+    Create::<T>::create(lhs.num_rows(), rhs.num_cols(), |i, j| {
+        let (a, b) = block_range(&lb, i, n);
+        let (c, d) = block_range(&rb, j, n);
+        let mut acc: T = Semiring::zero();
+        for k in range(cmp::max(a, c), cmp::min(b, d)) {
+            acc = acc + lhs.get(i, k) * rhs.get(k, j)
+        }
+        acc
+    })
+}
+
+// alpha x + beta y, without reading y when beta is zero.
+#[inline(always)]
+fn axpby<T: Semiring>(alpha: &T, x: T, beta: &T, y: &T) -> T {
+    if beta.is_zero() { *alpha * x } else { *alpha * x + *beta * *y }
+}
+
+// y = alpha A x + beta y. When beta is zero, y is only written, as in
+// BLAS. Matrices that expose contiguous storage get a loop over the
+// raw data; row-major storage takes a dot product per row, while
+// column-major storage (such as a TransposeMatrix of a Matrix)
+// accumulates one column at a time.
+pub fn gemv<T: Semiring, M: BasicMatrix<T>, V: Vector<T>>
+(alpha: T, A: &M, x: &V, beta: T, y: &mut DenseVector<T>)
+{
+    let N = A.num_rows();
+    let K = A.num_cols();
+    if K != x.len() || N != y.len() {
+        fail!(format!("Incompatible sizes. A: {:?}, x: {:?}, y: {:?}",
+                   (N, K), x.len(), y.len()))
+    }
+
+    let xs: Vec<T> = range(0, K).map(|j| x.get(j)).collect();
+    let xs = xs.as_slice();
+    let ys = y.as_mut_slice();
+
+    match (A.row_major_data(), A.col_major_data()) {
+        (Some(data), _) => {
+            for i in range(0, N) {
+                let row = data.slice(i * K, (i + 1) * K);
+                let mut acc: T = Semiring::zero();
+                for j in range(0, K) {
+                    acc = acc + row[j] * xs[j];
+                }
+                ys[i] = axpby(&alpha, acc, &beta, &ys[i]);
+            }
+        }
+        (None, Some(data)) => {
+            let mut acc: Vec<T> = Vec::from_elem(N, Semiring::zero());
+            for j in range(0, K) {
+                let column = data.slice(j * N, (j + 1) * N);
+                let xj = xs[j].clone();
+                for (a, c) in acc.mut_iter().zip(column.iter()) {
+                    *a = *a + *c * xj;
+                }
+            }
+            for (yi, a) in ys.mut_iter().zip(acc.move_iter()) {
+                *yi = axpby(&alpha, a, &beta, yi);
+            }
+        }
+        (None, None) => {
+            for i in range(0, N) {
+                let mut acc: T = Semiring::zero();
+                for j in range(0, K) {
+                    acc = acc + A.get(i, j) * xs[j];
+                }
+                ys[i] = axpby(&alpha, acc, &beta, &ys[i]);
+            }
+        }
+    }
+}
+
+// Whether a BLAS style routine uses an operand as is or transposed.
+#[deriving(Clone, PartialEq, Show)]
+pub enum TransposeFlag {
+    NoTrans,
+    Trans
+}
+
+// The (i, j) entry of op(A).
+#[inline(always)]
+fn op_get<T, M: BasicMatrix<T>>(t: TransposeFlag, A: &M, i: uint, j: uint) -> T {
+    match t {
+        NoTrans => A.get(i, j),
+        Trans => A.get(j, i)
+    }
+}
+
+// The dimensions of op(A).
+fn op_dims<T, M: BasicMatrix<T>>(t: TransposeFlag, A: &M) -> (uint, uint) {
+    match t {
+        NoTrans => (A.num_rows(), A.num_cols()),
+        Trans => (A.num_cols(), A.num_rows())
+    }
+}
+
+// Checks the shapes for C = op(A) op(B) and returns (m, n, k), where
+// op(A) is m x k and op(B) is k x n.
+pub fn gemm_dims<T, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Dest: BasicMatrix<T>>
+(ta: TransposeFlag, A: &LHS, tb: TransposeFlag, B: &RHS, C: &Dest)
+    -> (uint, uint, uint)
+{
+    let (m, k) = op_dims(ta, A);
+    let (kb, n) = op_dims(tb, B);
+    if k != kb || m != C.num_rows() || n != C.num_cols() {
+        fail!(format!("Incompatible matrix sizes. A: {:?}, B: {:?}, C: {:?}",
+                   (m, k), (kb, n), (C.num_rows(), C.num_cols())))
+    }
+    (m, n, k)
+}
+
+// C[rows, cols] = alpha op(A)[rows, inner] op(B)[inner, cols]
+//                 + beta C[rows, cols]
+fn gemm_kernel<T: Semiring, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Dest: BasicMatrix<T>>
+(ta: TransposeFlag, tb: TransposeFlag,
+ alpha: &T, A: &LHS, B: &RHS, beta: &T, C: &mut Dest,
+ rows: (uint, uint), cols: (uint, uint), inner: (uint, uint))
+{
+    let (i0, i1) = rows;
+    let (j0, j1) = cols;
+    let (k0, k1) = inner;
+    for i in range(i0, i1) {
+        for j in range(j0, j1) {
+            let mut acc: T = Semiring::zero();
+            for k in range(k0, k1) {
+                acc = acc + op_get(ta, A, i, k) * op_get(tb, B, k, j);
+            }
+            let c = if beta.is_zero() { Semiring::zero() } else { C.get(i, j) };
+            C.set(i, j, axpby(alpha, acc, beta, &c));
+        }
+    }
+}
+
+// General matrix multiply in place, C = alpha op(A) op(B) + beta C.
+// When beta is zero, C is only written, as in BLAS.
+pub fn gemm<T: Semiring, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Dest: BasicMatrix<T>>
+(ta: TransposeFlag, tb: TransposeFlag,
+ alpha: T, A: &LHS, B: &RHS, beta: T, C: &mut Dest)
+{
+    let (m, n, k) = gemm_dims(ta, A, tb, B, C);
+    gemm_kernel(ta, tb, &alpha, A, B, &beta, C, (0, m), (0, n), (0, k));
+}
+
+// Cache blocked gemm. This recursively halves the largest of the three
+// dimensions until the block fits under the cutoff, writing each block
+// straight into C. Only the first half of a split inner dimension
+// applies beta; the second half accumulates onto it.
+pub fn gemm_blocked<T: Semiring, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Dest: BasicMatrix<T>>
+(ta: TransposeFlag, tb: TransposeFlag,
+ alpha: T, A: &LHS, B: &RHS, beta: T, C: &mut Dest)
+{
+    let (m, n, k) = gemm_dims(ta, A, tb, B, C);
+    gemm_rec(ta, tb, &alpha, A, B, &beta, C, (0, m), (0, n), (0, k));
+}
+
+fn gemm_rec<T: Semiring, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Dest: BasicMatrix<T>>
+(ta: TransposeFlag, tb: TransposeFlag,
+ alpha: &T, A: &LHS, B: &RHS, beta: &T, C: &mut Dest,
+ rows: (uint, uint), cols: (uint, uint), inner: (uint, uint))
+{
+    static CUTOFF: uint = 32;
+
+    let (i0, i1) = rows;
+    let (j0, j1) = cols;
+    let (k0, k1) = inner;
+    let m = i1 - i0;
+    let n = j1 - j0;
+    let k = k1 - k0;
+
+    if m <= CUTOFF && n <= CUTOFF && k <= CUTOFF {
+        gemm_kernel(ta, tb, alpha, A, B, beta, C, rows, cols, inner)
+    }
+    else if m >= cmp::max(n, k) {
+        let h = i0 + m / 2;
+        gemm_rec(ta, tb, alpha, A, B, beta, C, (i0, h), cols, inner);
+        gemm_rec(ta, tb, alpha, A, B, beta, C, (h, i1), cols, inner);
+    }
+    else if n >= k {
+        let h = j0 + n / 2;
+        gemm_rec(ta, tb, alpha, A, B, beta, C, rows, (j0, h), inner);
+        gemm_rec(ta, tb, alpha, A, B, beta, C, rows, (h, j1), inner);
+    }
+    else {
+        let h = k0 + k / 2;
+        let one: T = Semiring::one();
+        gemm_rec(ta, tb, alpha, A, B, beta, C, rows, cols, (k0, h));
+        gemm_rec(ta, tb, alpha, A, B, &one, C, rows, cols, (h, k1));
+    }
+}
+
+// Symmetric rank-k update, C = alpha A At + beta C for NoTrans, or
+// C = alpha At A + beta C for Trans. Since the result is symmetric,
+// only the `uplo` triangle of C is computed and the other triangle is
+// left untouched.
+pub fn syrk<T: Semiring, M: BasicMatrix<T>, Dest: BasicMatrix<T>>
+(uplo: Triangle, trans: TransposeFlag, alpha: T, A: &M, beta: T, C: &mut Dest)
+{
+    let (n, k) = op_dims(trans, A);
+    if C.num_rows() != n || C.num_cols() != n {
+        fail!(format!("Incompatible matrix sizes. A: {:?}, C: {:?}",
+                   (n, k), (C.num_rows(), C.num_cols())))
+    }
+
+    for i in range(0, n) {
+        let (j0, j1) = match uplo {
+            Lower => (0, i + 1),
+            Upper => (i, n)
+        };
+        for j in range(j0, j1) {
+            let mut acc: T = Semiring::zero();
+            for l in range(0, k) {
+                acc = acc + op_get(trans, A, i, l) * op_get(trans, A, j, l);
+            }
+            let c = if beta.is_zero() { Semiring::zero() } else { C.get(i, j) };
+            C.set(i, j, axpby(&alpha, acc, &beta, &c));
+        }
+    }
+}
+
+// Which side a triangular operand multiplies from.
+#[deriving(Clone, PartialEq, Show)]
+pub enum Side {
+    Left,
+    Right
+}
+
+// Whether a triangular operand has an implicit unit diagonal.
+#[deriving(Clone, PartialEq, Show)]
+pub enum Diag {
+    NonUnit,
+    Unit
+}
+
+// The (i, j) entry of op(A) inside its triangle, with a unit diagonal
+// substituted when asked for.
+#[inline(always)]
+fn tri_get<T: Semiring, M: BasicMatrix<T>>(ta: TransposeFlag, diag: Diag, A: &M, i: uint, j: uint) -> T {
+    if i == j && diag == Unit { Semiring::one() } else { op_get(ta, A, i, j) }
+}
+
+// Triangular matrix multiply in place. B = alpha op(A) B for Left, or
+// B = alpha B op(A) for Right, where A is triangular and only its
+// `uplo` triangle is read. The rows (or columns) of B are overwritten
+// in an order that never reads one that has already been updated.
+pub fn trmm<T: Semiring, M: BasicMatrix<T>, Dest: BasicMatrix<T>>
+(side: Side, uplo: Triangle, ta: TransposeFlag, diag: Diag,
+ alpha: T, A: &M, B: &mut Dest)
+{
+    assert!(A.num_rows() == A.num_cols());
+    let n = A.num_rows();
+    let eff = if ta == NoTrans { uplo } else { uplo.flip() };
+
+    match side {
+        Left => {
+            assert!(B.num_rows() == n);
+            let rows: Vec<uint> = match eff {
+                Lower => range(0, n).rev().collect(),
+                Upper => range(0, n).collect()
+            };
+            for &i in rows.iter() {
+                let (k0, k1) = match eff { Lower => (0, i + 1), Upper => (i, n) };
+                for j in range(0, B.num_cols()) {
+                    let mut acc: T = Semiring::zero();
+                    for k in range(k0, k1) {
+                        acc = acc + tri_get(ta, diag, A, i, k) * B.get(k, j);
+                    }
+                    B.set(i, j, alpha * acc);
+                }
+            }
+        }
+        Right => {
+            assert!(B.num_cols() == n);
+            let cols: Vec<uint> = match eff {
+                Lower => range(0, n).collect(),
+                Upper => range(0, n).rev().collect()
+            };
+            for &j in cols.iter() {
+                let (k0, k1) = match eff { Lower => (j, n), Upper => (0, j + 1) };
+                for i in range(0, B.num_rows()) {
+                    let mut acc: T = Semiring::zero();
+                    for k in range(k0, k1) {
+                        acc = acc + B.get(i, k) * tri_get(ta, diag, A, k, j);
+                    }
+                    B.set(i, j, alpha * acc);
+                }
+            }
+        }
+    }
+}
+
+// Triangular solve with a matrix right hand side, in place. Solves
+// op(A) X = alpha B for Left, or X op(A) = alpha B for Right, and
+// overwrites B with X. Only the `uplo` triangle of A is read.
+//
+// This is blocked: the index range of A is halved recursively, and
+// the solved half updates the other half of B before it is solved.
+pub fn trsm<T: Field, M: BasicMatrix<T>, Dest: BasicMatrix<T>>
+(side: Side, uplo: Triangle, ta: TransposeFlag, diag: Diag,
+ alpha: T, A: &M, B: &mut Dest)
+{
+    assert!(A.num_rows() == A.num_cols());
+    let n = A.num_rows();
+    match side {
+        Left => assert!(B.num_rows() == n),
+        Right => assert!(B.num_cols() == n)
+    }
+
+    if alpha != Semiring::one() {
+        mat_x_inplace(B, alpha);
+    }
+
+    let eff = if ta == NoTrans { uplo } else { uplo.flip() };
+    trsm_rec(side, eff, ta, diag, A, B, 0, n);
+}
+
+fn trsm_rec<T: Field, M: BasicMatrix<T>, Dest: BasicMatrix<T>>
+(side: Side, eff: Triangle, ta: TransposeFlag, diag: Diag,
+ A: &M, B: &mut Dest, lo: uint, hi: uint)
+{
+    static CUTOFF: uint = 32;
+
+    if hi - lo <= CUTOFF {
+        return trsm_unblocked(side, eff, ta, diag, A, B, lo, hi)
+    }
+
+    let mid = lo + (hi - lo) / 2;
+    match (side, eff) {
+        (Left, Lower) => {
+            trsm_rec(side, eff, ta, diag, A, B, lo, mid);
+            trsm_update_rows(ta, A, B, (mid, hi), (lo, mid));
+            trsm_rec(side, eff, ta, diag, A, B, mid, hi);
+        }
+        (Left, Upper) => {
+            trsm_rec(side, eff, ta, diag, A, B, mid, hi);
+            trsm_update_rows(ta, A, B, (lo, mid), (mid, hi));
+            trsm_rec(side, eff, ta, diag, A, B, lo, mid);
+        }
+        (Right, Lower) => {
+            trsm_rec(side, eff, ta, diag, A, B, mid, hi);
+            trsm_update_cols(ta, A, B, (lo, mid), (mid, hi));
+            trsm_rec(side, eff, ta, diag, A, B, lo, mid);
+        }
+        (Right, Upper) => {
+            trsm_rec(side, eff, ta, diag, A, B, lo, mid);
+            trsm_update_cols(ta, A, B, (mid, hi), (lo, mid));
+            trsm_rec(side, eff, ta, diag, A, B, mid, hi);
+        }
+    }
+}
+
+// B[rows, :] -= op(A)[rows, solved] B[solved, :]
+fn trsm_update_rows<T: Ring, M: BasicMatrix<T>, Dest: BasicMatrix<T>>
+(ta: TransposeFlag, A: &M, B: &mut Dest, rows: (uint, uint), solved: (uint, uint))
+{
+    let (r0, r1) = rows;
+    let (k0, k1) = solved;
+    for i in range(r0, r1) {
+        for j in range(0, B.num_cols()) {
+            let mut acc = B.get(i, j);
+            for k in range(k0, k1) {
+                acc = acc - op_get(ta, A, i, k) * B.get(k, j);
+            }
+            B.set(i, j, acc);
+        }
+    }
+}
+
+// B[:, cols] -= B[:, solved] op(A)[solved, cols]
+fn trsm_update_cols<T: Ring, M: BasicMatrix<T>, Dest: BasicMatrix<T>>
+(ta: TransposeFlag, A: &M, B: &mut Dest, cols: (uint, uint), solved: (uint, uint))
+{
+    let (c0, c1) = cols;
+    let (k0, k1) = solved;
+    for i in range(0, B.num_rows()) {
+        for j in range(c0, c1) {
+            let mut acc = B.get(i, j);
+            for k in range(k0, k1) {
+                acc = acc - B.get(i, k) * op_get(ta, A, k, j);
+            }
+            B.set(i, j, acc);
+        }
+    }
+}
+
+// Substitution over the indices lo to hi of op(A), assuming everything
+// outside that range has already been folded into B.
+fn trsm_unblocked<T: Field, M: BasicMatrix<T>, Dest: BasicMatrix<T>>
+(side: Side, eff: Triangle, ta: TransposeFlag, diag: Diag,
+ A: &M, B: &mut Dest, lo: uint, hi: uint)
+{
+    match side {
+        Left => {
+            let rows: Vec<uint> = match eff {
+                Lower => range(lo, hi).collect(),
+                Upper => range(lo, hi).rev().collect()
+            };
+            for &i in rows.iter() {
+                let (k0, k1) = match eff { Lower => (lo, i), Upper => (i + 1, hi) };
+                for j in range(0, B.num_cols()) {
+                    let mut x = B.get(i, j);
+                    for k in range(k0, k1) {
+                        x = x - op_get(ta, A, i, k) * B.get(k, j);
+                    }
+                    B.set(i, j, x / tri_get(ta, diag, A, i, i));
+                }
+            }
+        }
+        Right => {
+            let cols: Vec<uint> = match eff {
+                Lower => range(lo, hi).rev().collect(),
+                Upper => range(lo, hi).collect()
+            };
+            for &j in cols.iter() {
+                let (k0, k1) = match eff { Lower => (j + 1, hi), Upper => (lo, j) };
+                for i in range(0, B.num_rows()) {
+                    let mut x = B.get(i, j);
+                    for k in range(k0, k1) {
+                        x = x - B.get(i, k) * op_get(ta, A, k, j);
+                    }
+                    B.set(i, j, x / tri_get(ta, diag, A, j, j));
+                }
+            }
+        }
+    }
+}
+
 // M -> (A, B, C, D)
-fn subdivide<'a, T: Num, M: BasicMatrix<T>>(M: &'a M)
+fn subdivide<'a, T, M: BasicMatrix<T>>(M: &'a M)
     -> (SubMatrix<'a, T, M>, SubMatrix<'a, T, M>,
         SubMatrix<'a, T, M>, SubMatrix<'a, T, M>)
 {
@@ -59,7 +546,7 @@ fn subdivide<'a, T: Num, M: BasicMatrix<T>>(M: &'a M)
     (A, B, C, D)
 }
 
-pub fn mat_mul_blocked<T: Num + num::FromPrimitive, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Res: BasicMatrix<T> + Create<T>> (lhs: &LHS, rhs: &RHS) -> Res
+pub fn mat_mul_blocked<T: Semiring, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Res: BasicMatrix<T> + Create<T>> (lhs: &LHS, rhs: &RHS) -> Res
 {
     if lhs.num_cols() != rhs.num_rows() {
         fail!(format!("Incompatible matrix sizes. LHS: {:?}, RHS: {:?}",
@@ -81,7 +568,7 @@ pub fn mat_mul_blocked<T: Num + num::FromPrimitive, LHS: BasicMatrix<T>, RHS: Ba
         let (E, F, G, H) = subdivide(rhs);
 
         let res: Res = Create::<T>::create(lhs.num_rows(), rhs.num_cols(),
-                                           |_, _| FromPrimitive::from_int(0).unwrap());
+                                           |_, _| Semiring::zero());
         {
             let (mut An, mut Bn, mut Cn, mut Dn) = subdivide(&res);
 
@@ -119,7 +606,59 @@ pub fn mat_mul_blocked<T: Num + num::FromPrimitive, LHS: BasicMatrix<T>, RHS: Ba
     }
 }
 
-pub fn mat_add<T: Num, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Res: BasicMatrix<T> + Create<T>> (lhs: &LHS, rhs: &RHS) -> Res
+// A^k by repeated squaring, so it takes about 2 log2(k) products. A^0
+// is the identity.
+pub fn mat_pow<T: Semiring, M: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>(A: &M, k: uint) -> R {
+    assert!(A.num_rows() == A.num_cols());
+
+    let mut result: R = identity(A.num_rows());
+    let mut base: R = convert(A);
+    let mut k = k;
+    while k > 0 {
+        if k & 1 == 1 {
+            result = mat_mul(&result, &base);
+        }
+        k >>= 1;
+        if k > 0 {
+            base = mat_mul(&base, &base);
+        }
+    }
+    result
+}
+
+// The Kleene star A* = I + A + A^2 + ..., for semirings with
+// idempotent addition in which the series settles within n - 1 terms.
+// Over MinPlus this holds when there are no negative cycles, and A*(i,
+// j) is the length of the shortest path from i to j; over MaxPlus it
+// holds when there are no positive cycles, and gives longest paths.
+//
+// With idempotent addition (I + A)^m = I + A + ... + A^m, so this
+// squares I + A until m reaches n - 1 or a square changes nothing.
+pub fn closure<T: Semiring, M: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>(A: &M) -> R {
+    assert!(A.num_rows() == A.num_cols());
+    let n = A.num_rows();
+
+    let I: R = identity(n);
+    let mut C: R = mat_add(&I, A);
+    let mut m = 1;
+    while m + 1 < n {
+        let C2: R = mat_mul(&C, &C);
+        if same_entries(&C, &C2) {
+            break
+        }
+        C = C2;
+        m *= 2;
+    }
+    C
+}
+
+fn same_entries<T: PartialEq, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>>(A: &LHS, B: &RHS) -> bool {
+    range(0, A.num_rows()).all(|i| {
+        range(0, A.num_cols()).all(|j| A.get(i, j) == B.get(i, j))
+    })
+}
+
+pub fn mat_add<T: Semiring, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Res: BasicMatrix<T> + Create<T>> (lhs: &LHS, rhs: &RHS) -> Res
 {
     if lhs.num_cols() != rhs.num_cols() || lhs.num_rows() != rhs.num_rows() {
         fail!("Incompatible matrix sizes")
@@ -130,14 +669,14 @@ pub fn mat_add<T: Num, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Res: BasicMatri
     })
 }
 
-pub fn mat_add_inplace<T: Num, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>>
+pub fn mat_add_inplace<T: Semiring, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>>
 (lhs: &mut LHS, rhs: &RHS) {
     for_each(lhs, |i, j, x| {
         x + rhs.get(i, j)
     })
 }
 
-pub fn mat_add_into<T: Num, Dest: BasicMatrix<T>, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>>
+pub fn mat_add_into<T: Semiring, Dest: BasicMatrix<T>, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>>
 (dest: &mut Dest, lhs: &LHS, rhs: &RHS) {
     assert!(dest.num_rows() == lhs.num_rows());
     assert!(dest.num_cols() == lhs.num_cols());
@@ -148,7 +687,7 @@ pub fn mat_add_into<T: Num, Dest: BasicMatrix<T>, LHS: BasicMatrix<T>, RHS: Basi
     })
 }
 
-pub fn mat_sub<T: Num, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Res: BasicMatrix<T> + Create<T>> (lhs: &LHS, rhs: &RHS) -> Res
+pub fn mat_sub<T: Ring, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Res: BasicMatrix<T> + Create<T>> (lhs: &LHS, rhs: &RHS) -> Res
 {
     if lhs.num_cols() != rhs.num_cols() || lhs.num_rows() != rhs.num_rows() {
         fail!(format!("Incompatible matrix sizes. LHS: {:?}, RHS: {:?}",
@@ -161,7 +700,7 @@ pub fn mat_sub<T: Num, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Res: BasicMatri
     })
 }
 
-pub fn mat_x_inplace<T: Num, M: BasicMatrix<T>>(A: &mut M, x: T) {
+pub fn mat_x_inplace<T: Semiring, M: BasicMatrix<T>>(A: &mut M, x: T) {
     for_each(A, |_i, _j, y| x * y);
 }
 
@@ -171,17 +710,18 @@ pub fn transpose<T, M: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>(m: &M) ->
     })
 }
 
-pub fn cholesky_seq_inplace_raw<M: BasicMatrix<f64>>(A: &mut M, start: uint) {
+// Only the lower triangle of A is read and written.
+pub fn cholesky_seq_inplace_raw<T: RealField, M: BasicMatrix<T>>(A: &mut M, start: uint) {
     assert!(A.num_rows() == A.num_cols());
     let N = A.num_rows();
     for k in range(start, N) {
-        let Akk = A.get(k, k);
+        let Lkk = A.get(k, k).sqrt();
 
-        A.set(k, k, Akk.sqrt());
+        A.set(k, k, Lkk.clone());
 
         for i in range(k + 1, N) {
             let Aik = A.get(i, k);
-            A.set(i, k, Aik / Akk);
+            A.set(i, k, Aik / Lkk);
         }
 
         for i in range(k + 1, N) {
@@ -204,21 +744,55 @@ pub fn for_each<T, M: BasicMatrix<T>>(A: &mut M, f: |uint, uint, T| -> T) {
     }
 }
 
-pub fn cholesky_seq_inplace<M: BasicMatrix<f64>>(A: &mut M) {
-    cholesky_seq_inplace_start::<M>(A, 0);
+pub fn cholesky_seq_inplace<T: RealField, M: BasicMatrix<T>>(A: &mut M) {
+    cholesky_seq_inplace_start(A, 0);
 }
 
-pub fn cholesky_seq_inplace_start<M: BasicMatrix<f64>>(A: &mut M,
-                                                       start: uint) {
-    cholesky_seq_inplace_raw::<M>(A, start);
+pub fn cholesky_seq_inplace_start<T: RealField, M: BasicMatrix<T>>(A: &mut M,
+                                                                   start: uint) {
+    cholesky_seq_inplace_raw(A, start);
 
     for i in range(start, A.num_rows()) {
         for j in range(i + 1, A.num_cols()) {
-            A.set(i, j, num::from_int(0).unwrap())
+            A.set(i, j, Semiring::zero())
         }
     }    
 }
 
+// Cholesky factorization of a Hermitian positive definite matrix,
+// A = L Lh, in place. Only the lower triangle of A is read, and the
+// upper triangle is zeroed.
+pub fn cholesky_hermitian_inplace<T: Float, M: BasicMatrix<Complex<T>>>(A: &mut M) {
+    assert!(A.num_rows() == A.num_cols());
+    let N = A.num_rows();
+    for k in range(0, N) {
+        // The diagonal of a Hermitian matrix is real.
+        let Lkk = A.get(k, k).re.sqrt();
+        let d = Complex::new(Lkk, num::zero());
+        A.set(k, k, d);
+
+        for i in range(k + 1, N) {
+            let Aik = A.get(i, k);
+            A.set(i, k, Aik / d);
+        }
+
+        for i in range(k + 1, N) {
+            let Lik = A.get(i, k);
+            for j in range(k + 1, i + 1) {
+                let Ljk = A.get(j, k);
+                let Aij = A.get(i, j);
+                A.set(i, j, Aij - Lik * Ljk.conj());
+            }
+        }
+    }
+
+    for i in range(0, N) {
+        for j in range(i + 1, N) {
+            A.set(i, j, Semiring::zero())
+        }
+    }
+}
+
 
 pub fn concat_rows<T, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>(A: &LHS, B: &RHS) -> R {
     assert!(A.num_cols() == B.num_cols());
@@ -257,7 +831,7 @@ pub fn convert<T, M: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>(M: &M) -> R
     Create::<T>::create(M.num_rows(), M.num_cols(), |i, j| M.get(i, j))
 }
 
-pub fn inverse<T: Num + FromPrimitive, M: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>(M: &M) -> R {
+pub fn inverse<T: Field, M: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>(M: &M) -> R {
     // This basically does the blockwise inversion algorithm on the
     // Wikipedia page [1]. It's not a very efficient implementation,
     // since it ends up doing an absurd number of copies. It also
@@ -268,14 +842,27 @@ pub fn inverse<T: Num + FromPrimitive, M: BasicMatrix<T>, R: BasicMatrix<T> + Cr
 
     assert!(M.num_rows() == M.num_cols());
 
+    match M.diagonal_blocks() {
+        Some(offsets) => return block_diagonal_inverse(M, offsets.as_slice()),
+        None => {}
+    }
+
+    if M.triangle().is_some() {
+        return triangular::inverse(M)
+    }
+
     let N = M.num_rows();
 
     if N == 1 {
-        Create::<T>::create(1, 1, |i, j| num::from_int::<T>(1).unwrap() / M.get(i, j))
+        Create::<T>::create(1, 1, |i, j| {
+            let one: T = Semiring::one();
+            one / M.get(i, j)
+        })
     }
     else {
         let N2 = N / 2;
         let N2a = N - N2;
+        let one: T = Semiring::one();
 
         // the convert is needed to avoid infinite expansion of
         // monomorphic functions. The type voodoo that's currently
@@ -311,7 +898,7 @@ pub fn inverse<T: Num + FromPrimitive, M: BasicMatrix<T>, R: BasicMatrix<T> + Cr
         // new C
         //error!("C");
         let mut Cn: R = mat_mul(&Dn, &CAi);
-        mat_x_inplace(&mut Cn, num::from_int(-1).unwrap());
+        mat_x_inplace(&mut Cn, -one);
 
         // new B
         //error!("B");
@@ -324,7 +911,7 @@ pub fn inverse<T: Num + FromPrimitive, M: BasicMatrix<T>, R: BasicMatrix<T> + Cr
         let mut An: R = mat_mul(&Bn, &CAi);
         mat_add_inplace(&mut An, &Ai);
 
-        mat_x_inplace(&mut Bn, num::from_int(-1).unwrap());
+        mat_x_inplace(&mut Bn, -one);
 
         // Stitch it all back together.
         let top: R = concat_cols(&An, &Bn);
@@ -333,7 +920,226 @@ pub fn inverse<T: Num + FromPrimitive, M: BasicMatrix<T>, R: BasicMatrix<T> + Cr
     }
 }
 
-pub fn cholesky_blocked<M: BasicMatrix<f64>, R: BasicMatrix<f64> + Create<f64>>(M: &M) -> R {
+// Inverts each diagonal block on its own.
+fn block_diagonal_inverse<T: Field, M: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>
+(M: &M, offsets: &[uint]) -> R
+{
+    let inverses: Vec<Matrix<T>> = range(0, offsets.len() - 1).map(|k| {
+        let o = offsets[k];
+        let s = offsets[k + 1] - o;
+        let B: Matrix<T> = convert(&SubMatrix(M, o, o, s, s));
+        inverse(&B)
+    }).collect();
+
+    let N = M.num_rows();
+    Create::<T>::create(N, N, |i, j| {
+        let k = block_of(offsets, i);
+        let o = offsets[k];
+        if j >= o && j < offsets[k + 1] {
+            inverses.get(k).get(i - o, j - o)
+        }
+        else {
+            Semiring::zero()
+        }
+    })
+}
+
+// Solves A X = B. Triangular A is solved by substitution; otherwise
+// this goes through the inverse.
+pub fn solve<T: Field, M: BasicMatrix<T>, RHS: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>(A: &M, B: &RHS) -> R {
+    assert!(A.num_rows() == A.num_cols());
+    assert!(A.num_rows() == B.num_rows());
+
+    if A.triangle().is_some() {
+        triangular::solve(A, B)
+    }
+    else {
+        let Ai: R = inverse(A);
+        mat_mul(&Ai, B)
+    }
+}
+
+// Computes the determinant by Gaussian elimination, swapping in the
+// first nonzero pivot of each column. That is exact over exact fields
+// such as Rational; for floating point use determinant_pivoted.
+pub fn determinant<T: Field, M: BasicMatrix<T>>(A: &M) -> T {
+    determinant_by(A, |U, k| range(k, U.num_rows()).find(|&i| !U.get(i, k).is_zero()))
+}
+
+// The determinant by Gaussian elimination with partial pivoting, which
+// swaps in the largest pivot of each column to keep the multipliers at
+// most 1 in magnitude.
+pub fn determinant_pivoted<T: RealField, M: BasicMatrix<T>>(A: &M) -> T {
+    determinant_by(A, |U, k| {
+        let mut p = k;
+        for i in range(k + 1, U.num_rows()) {
+            if U.get(i, k).abs() > U.get(p, k).abs() {
+                p = i;
+            }
+        }
+        if U.get(p, k).is_zero() { None } else { Some(p) }
+    })
+}
+
+// Gaussian elimination, with `pivot` choosing the row to swap into row
+// k, or None if column k has no nonzero entry left.
+fn determinant_by<T: Field, M: BasicMatrix<T>>(A: &M, pivot: |&Matrix<T>, uint| -> Option<uint>) -> T {
+    assert!(A.num_rows() == A.num_cols());
+
+    // The product of the determinants of the diagonal blocks.
+    match A.diagonal_blocks() {
+        Some(offsets) => {
+            let mut det: T = Semiring::one();
+            for b in range(0, offsets.len() - 1) {
+                let o = *offsets.get(b);
+                let s = *offsets.get(b + 1) - o;
+                let B: Matrix<T> = convert(&SubMatrix(A, o, o, s, s));
+                det = det * determinant_by(&B, |U, k| pivot(U, k));
+            }
+            return det
+        }
+        None => {}
+    }
+
+    if A.triangle().is_some() {
+        return triangular::determinant(A)
+    }
+
+    let N = A.num_rows();
+    let mut U: Matrix<T> = convert(A);
+    let mut det: T = Semiring::one();
+    for k in range(0, N) {
+        let p = match pivot(&U, k) {
+            Some(p) => p,
+            None => return Semiring::zero()
+        };
+        if p != k {
+            swap_rows(&mut U, k, p, k);
+            det = -det;
+        }
+
+        let Ukk = U.get(k, k);
+        det = det * Ukk;
+        for i in range(k + 1, N) {
+            let l = U.get(i, k) / Ukk;
+            for j in range(k + 1, N) {
+                let Uij = U.get(i, j);
+                U.set(i, j, Uij - l * U.get(k, j));
+            }
+        }
+    }
+    det
+}
+
+// Swaps rows a and b of A, from column `from` on.
+pub fn swap_rows<T, M: BasicMatrix<T>>(A: &mut M, a: uint, b: uint, from: uint) {
+    for j in range(from, A.num_cols()) {
+        let t = A.get(a, j);
+        A.set(a, j, A.get(b, j));
+        A.set(b, j, t);
+    }
+}
+
+// Solves A X = B by Gauss-Jordan elimination, row reducing [A | B] to
+// [I | X]. No rounding happens over exact fields such as Rational, so
+// the result is exact there; for floating point, which would want
+// larger pivots, use solve. Fails if A is singular.
+pub fn gauss_jordan_solve<T: Field, M: BasicMatrix<T>, RHS: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>(A: &M, B: &RHS) -> R {
+    assert!(A.num_rows() == A.num_cols());
+    assert!(A.num_rows() == B.num_rows());
+
+    let N = A.num_rows();
+    let mut E: Matrix<T> = concat_cols(A, B);
+    let pivots = row_reduce(&mut E);
+
+    // A is nonsingular exactly when its columns are the first N pivots.
+    if N > 0 && (pivots.len() < N || *pivots.get(N - 1) != N - 1) {
+        fail!("Matrix is singular.")
+    }
+    Create::<T>::create(N, B.num_cols(), |i, j| E.get(i, N + j))
+}
+
+// The inverse by Gauss-Jordan elimination. See gauss_jordan_solve.
+pub fn gauss_jordan_inverse<T: Field, M: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>(A: &M) -> R {
+    let I: Matrix<T> = identity(A.num_rows());
+    gauss_jordan_solve(A, &I)
+}
+
+// Reduces A in place to reduced row echelon form by Gauss-Jordan
+// elimination, pivoting on the first nonzero entry of each column, and
+// returns the pivot columns. This is exact over exact fields such as
+// Rational.
+pub fn row_reduce<T: Field, M: BasicMatrix<T>>(A: &mut M) -> Vec<uint> {
+    let N = A.num_rows();
+    let W = A.num_cols();
+    let mut pivots = Vec::new();
+    let mut r = 0;
+    for c in range(0, W) {
+        if r == N {
+            break
+        }
+        let p = match range(r, N).find(|&i| !A.get(i, c).is_zero()) {
+            Some(p) => p,
+            None => continue
+        };
+        // Rows r and below are zero left of column c.
+        if p != r {
+            swap_rows(A, r, p, c);
+        }
+
+        let one: T = Semiring::one();
+        let d = one / A.get(r, c);
+        for j in range(c, W) {
+            let Arj = A.get(r, j);
+            A.set(r, j, Arj * d);
+        }
+
+        for i in range(0, N) {
+            let f = A.get(i, c);
+            if i == r || f.is_zero() {
+                continue
+            }
+            for j in range(c, W) {
+                let Aij = A.get(i, j);
+                A.set(i, j, Aij - f * A.get(r, j));
+            }
+        }
+        pivots.push(c);
+        r += 1;
+    }
+    pivots
+}
+
+pub fn rank<T: Field, M: BasicMatrix<T>>(A: &M) -> uint {
+    let mut E: Matrix<T> = convert(A);
+    row_reduce(&mut E).len()
+}
+
+// A basis of the nullspace of A, as the columns of an n x (n - rank)
+// matrix where n is the number of columns of A. There is one basis
+// vector per non-pivot column of the row reduced A.
+pub fn nullspace<T: Field, M: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>(A: &M) -> R {
+    let n = A.num_cols();
+    let mut E: Matrix<T> = convert(A);
+    let pivots = row_reduce(&mut E);
+
+    let mut is_pivot = Vec::from_elem(n, false);
+    for &c in pivots.iter() {
+        *is_pivot.get_mut(c) = true;
+    }
+    let free: Vec<uint> = range(0, n).filter(|&c| !*is_pivot.get(c)).collect();
+
+    let mut K: R = zero_matrix(n, free.len());
+    for (k, &f) in free.iter().enumerate() {
+        K.set(f, k, Semiring::one());
+        for (r, &c) in pivots.iter().enumerate() {
+            K.set(c, k, -E.get(r, f));
+        }
+    }
+    K
+}
+
+pub fn cholesky_blocked<T: RealField, M: BasicMatrix<T>>(M: &M) -> LowerTriangular<T> {
     /*
     A recursive blocked Cholesky factorization.
 
@@ -354,6 +1160,9 @@ pub fn cholesky_blocked<M: BasicMatrix<f64>, R: BasicMatrix<f64> + Create<f64>>(
     I derived this with some help from
     http://www.netlib.org/utk/papers/factor/node9.html
 
+    The factor comes back as a LowerTriangular, so that solve, inverse
+    and mat_mul take their triangular paths on it. The off diagonal
+    blocks are rectangular, so they are worked on as dense matrices.
     */
 
     assert!(M.num_rows() == M.num_cols());
@@ -362,34 +1171,55 @@ pub fn cholesky_blocked<M: BasicMatrix<f64>, R: BasicMatrix<f64> + Create<f64>>(
     static BLOCK_SIZE: uint = 1;
 
     if N <= BLOCK_SIZE {
-        let mut M = convert(M);
-        cholesky_seq_inplace::<R>(&mut M);
-        M
+        let mut L: Matrix<T> = convert(M);
+        cholesky_seq_inplace_raw(&mut L, 0);
+        convert(&L)
     }
     else {
         let N2 = N / 2;
         let N2a = N - N2;
 
-        let A: R = convert(&SubMatrix(M, 0, 0, N2, N2));
+        let A: Matrix<T> = convert(&SubMatrix(M, 0, 0, N2, N2));
         //let B = SubMatrix(M, 0,  N2, N2,  N2a);
         let C = SubMatrix(M, N2, 0,  N2a, N2);
         let D = SubMatrix(M, N2, N2, N2a, N2a);
 
-        let Ac: R = cholesky_blocked(&A);
+        let Ac = cholesky_blocked(&A);
+        let one: T = Semiring::one();
 
-        let Aci: R = inverse::<f64, TransposeMatrix<f64, R>, R>(&TransposeMatrix(&Ac));
+        // CActi solves X Act = C, so it comes from a triangular solve
+        // rather than from inverting Act.
+        let mut CAci: Matrix<T> = convert(&C);
+        trsm(Right, Lower, Trans, NonUnit, one.clone(), &Ac, &mut CAci);
 
-        let CAci: R = mat_mul(&C, &Aci);
+        // Only the lower triangle of D - CAci(CAci)t is read below.
+        let mut Dn: Matrix<T> = convert(&D);
+        syrk(Lower, NoTrans, -one, &CAci, one.clone(), &mut Dn);
+        let Dc = cholesky_blocked(&Dn);
 
-        let mut Dn: R = mat_mul::<f64, R, TransposeMatrix<f64, R>, R>(&CAci, &TransposeMatrix(&CAci));
-        Dn = mat_sub(&D, &Dn);
-        Dn = cholesky_blocked(&Dn);
-
-        let Z = zero_matrix::<f64, R>(N2, N2a);
+        // Only the lower triangle is asked for, so the zero upper right
+        // block is never built.
+        Create::create(N, N, |i, j| {
+            if i < N2 {
+                Ac.get(i, j)
+            }
+            else if j < N2 {
+                CAci.get(i - N2, j)
+            }
+            else {
+                Dc.get(i - N2, j - N2)
+            }
+        })
+    }
+}
 
-        let top: R = concat_cols(&Ac, &Z);
-        let bot: R = concat_cols(&CAci, &Dn);
+// Returns P A Pt, where row and column k of the result are row and
+// column perm[k] of A.
+pub fn permute_symmetric<T, M: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>(A: &M, perm: &[uint]) -> R {
+    assert!(A.num_rows() == A.num_cols());
+    assert!(perm.len() == A.num_rows());
 
-        concat_rows(&top, &bot)
-    }
+    Create::<T>::create(A.num_rows(), A.num_cols(), |i, j| {
+        A.get(perm[i], perm[j])
+    })
 }
@@ -0,0 +1,197 @@
+// Band storage for matrices whose nonzeros lie near the diagonal.
+
+#![allow(uppercase_variables)]
+
+use std::cmp;
+
+use matrix::{BasicMatrix, Create, Matrix, Vector, Semiring};
+
+// A matrix with `kl` subdiagonals and `ku` superdiagonals. Each row
+// stores the kl + ku + 1 entries of the band, so entry (i, j) lives at
+// data[i * (kl + ku + 1) + kl + j - i]. Slots that fall outside the
+// matrix are kept at zero.
+#[deriving(Clone)]
+pub struct BandMatrix<T> {
+    rows: uint,
+    cols: uint,
+    kl: uint,
+    ku: uint,
+
+    data: Vec<T>
+}
+
+impl<T: Semiring> BandMatrix<T> {
+    /// Creates a zero `rows` x `cols` matrix with the given lower and
+    /// upper bandwidth.
+    pub fn new(rows: uint, cols: uint, kl: uint, ku: uint) -> BandMatrix<T> {
+        BandMatrix {
+            rows: rows, cols: cols, kl: kl, ku: ku,
+            data: Vec::from_elem(rows * (kl + ku + 1), Semiring::zero())
+        }
+    }
+
+    /// Packs the band of a dense matrix, detecting the bandwidth.
+    pub fn from_dense<M: BasicMatrix<T>>(m: &M) -> BandMatrix<T> {
+        let (kl, ku) = bandwidth(m);
+        let mut b = BandMatrix::new(m.num_rows(), m.num_cols(), kl, ku);
+        for i in range(0, b.rows) {
+            for j in range(b.first_col(i), b.last_col(i)) {
+                b.set(i, j, m.get(i, j));
+            }
+        }
+        b
+    }
+
+    /// Returns the number of subdiagonals.
+    pub fn lower_bandwidth(&self) -> uint { self.kl }
+    /// Returns the number of superdiagonals.
+    pub fn upper_bandwidth(&self) -> uint { self.ku }
+
+    /// The first column of row i inside the band.
+    #[inline(always)]
+    pub fn first_col(&self, i: uint) -> uint {
+        if i > self.kl { i - self.kl } else { 0 }
+    }
+
+    /// One past the last column of row i inside the band.
+    #[inline(always)]
+    pub fn last_col(&self, i: uint) -> uint {
+        cmp::min(i + self.ku + 1, self.cols)
+    }
+
+    #[inline(always)]
+    fn in_band(&self, i: uint, j: uint) -> bool {
+        j + self.kl >= i && j <= i + self.ku
+    }
+
+    #[inline(always)]
+    fn offset(&self, i: uint, j: uint) -> uint {
+        i * (self.kl + self.ku + 1) + self.kl + j - i
+    }
+}
+
+impl<T: Semiring> BasicMatrix<T> for BandMatrix<T> {
+    #[inline(always)]
+    fn get(&self, i: uint, j: uint) -> T {
+        if i < self.rows && j < self.cols {
+            if self.in_band(i, j) {
+                self.data.as_slice()[self.offset(i, j)].clone()
+            }
+            else {
+                Semiring::zero()
+            }
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (self.rows, self.cols)))
+        }
+    }
+
+    #[inline(always)]
+    fn set(&mut self, i: uint, j: uint, x: T) {
+        if i < self.rows && j < self.cols {
+            if self.in_band(i, j) {
+                let k = self.offset(i, j);
+                self.data.as_mut_slice()[k] = x
+            }
+            else if !x.is_zero() {
+                fail!(format!("Entry {:?} is outside the band {:?}.",
+                              (i, j), (self.kl, self.ku)))
+            }
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (self.rows, self.cols)))
+        }
+    }
+
+    fn num_rows(&self) -> uint { self.rows }
+    fn num_cols(&self) -> uint { self.cols }
+}
+
+// The band is not known until every entry has been seen, so this
+// evaluates `init` into a dense buffer first.
+impl<T: Semiring> Create<T> for BandMatrix<T> {
+    fn create(i: uint, j: uint, init: |uint, uint| -> T) -> BandMatrix<T> {
+        let m: Matrix<T> = Create::create(i, j, init);
+        BandMatrix::from_dense(&m)
+    }
+}
+
+// Returns the lower and upper bandwidth of m, the distances of the
+// furthest nonzeros below and above the diagonal.
+pub fn bandwidth<T: Semiring, M: BasicMatrix<T>>(m: &M) -> (uint, uint) {
+    let mut kl = 0;
+    let mut ku = 0;
+    for i in range(0, m.num_rows()) {
+        for j in range(0, m.num_cols()) {
+            if !m.get(i, j).is_zero() {
+                if i > j && i - j > kl {
+                    kl = i - j
+                }
+                if j > i && j - i > ku {
+                    ku = j - i
+                }
+            }
+        }
+    }
+    (kl, ku)
+}
+
+// y = A x, touching only the band.
+pub fn mat_vec<T: Semiring, V: Vector<T>>(A: &BandMatrix<T>, x: &V) -> Vec<T> {
+    assert!(A.num_cols() == x.len());
+
+    Vec::from_fn(A.num_rows(), |i| {
+        let mut acc: T = Semiring::zero();
+        for j in range(A.first_col(i), A.last_col(i)) {
+            acc = acc + A.get(i, j) * x.get(j)
+        }
+        acc
+    })
+}
+
+// The product of two band matrices, which has lower bandwidth
+// kl1 + kl2 and upper bandwidth ku1 + ku2.
+pub fn mat_mul<T: Semiring>(lhs: &BandMatrix<T>, rhs: &BandMatrix<T>) -> BandMatrix<T>
+{
+    if lhs.num_cols() != rhs.num_rows() {
+        fail!(format!("Incompatible matrix sizes. LHS: {:?}, RHS: {:?}",
+                   (lhs.num_rows(), lhs.num_cols()),
+                   (rhs.num_rows(), rhs.num_cols())))
+    }
+
+    let mut res = BandMatrix::new(lhs.num_rows(), rhs.num_cols(),
+                                  lhs.kl + rhs.kl, lhs.ku + rhs.ku);
+    for i in range(0, lhs.num_rows()) {
+        for k in range(lhs.first_col(i), lhs.last_col(i)) {
+            let a = lhs.get(i, k);
+            for j in range(rhs.first_col(k), rhs.last_col(k)) {
+                let c = res.get(i, j);
+                res.set(i, j, c + a * rhs.get(k, j));
+            }
+        }
+    }
+    res
+}
+
+// The product of a band matrix with a general matrix.
+pub fn mat_mul_dense<T: Semiring, RHS: BasicMatrix<T>, Res: BasicMatrix<T> + Create<T>>
+(lhs: &BandMatrix<T>, rhs: &RHS) -> Res
+{
+    if lhs.num_cols() != rhs.num_rows() {
+        fail!(format!("Incompatible matrix sizes. LHS: {:?}, RHS: {:?}",
+                   (lhs.num_rows(), lhs.num_cols()),
+                   (rhs.num_rows(), rhs.num_cols())))
+    }
+
+    Create::<T>::create(lhs.num_rows(), rhs.num_cols(), |i, j| {
+        let mut acc: T = Semiring::zero();
+        for k in range(lhs.first_col(i), lhs.last_col(i)) {
+            acc = acc + lhs.get(i, k) * rhs.get(k, j)
+        }
+        acc
+    })
+}
@@ -0,0 +1,361 @@
+// Bit-packed matrices over the Booleans and over GF(2).
+//
+// Both use the same storage and differ only in how products add up:
+// Boolean sums are OR and GF(2) sums are XOR. The routines work a
+// whole 64 bit word of a row at a time.
+
+#![allow(uppercase_variables)]
+
+use std::cmp;
+
+use matrix::{BasicMatrix, Create};
+
+static WORD_BITS: uint = 64;
+
+// Rows are packed into 64 bit words, with column j of row i at bit
+// j % 64 of data[i * stride + j / 64]. Bits past the last column are
+// always zero.
+#[deriving(Clone, PartialEq, Show)]
+pub struct BitMatrix {
+    rows: uint,
+    cols: uint,
+    stride: uint,
+
+    data: Vec<u64>
+}
+
+impl BitMatrix {
+    /// Creates an all false `rows` x `cols` matrix.
+    pub fn new(rows: uint, cols: uint) -> BitMatrix {
+        let stride = (cols + WORD_BITS - 1) / WORD_BITS;
+        BitMatrix {
+            rows: rows,
+            cols: cols,
+            stride: stride,
+            data: Vec::from_elem(rows * stride, 0u64)
+        }
+    }
+
+    pub fn identity(n: uint) -> BitMatrix {
+        let mut I = BitMatrix::new(n, n);
+        for i in range(0, n) {
+            I.set(i, i, true);
+        }
+        I
+    }
+
+    /// The packed words of row i.
+    pub fn row_words<'a>(&'a self, i: uint) -> &'a [u64] {
+        self.data.slice(i * self.stride, (i + 1) * self.stride)
+    }
+
+    // The `w` <= 8 bits of row i starting at column j, which must be a
+    // multiple of 8 so that they sit in one word.
+    #[inline(always)]
+    fn bits(&self, i: uint, j: uint, w: uint) -> uint {
+        let word = self.data.as_slice()[i * self.stride + j / WORD_BITS];
+        ((word >> (j % WORD_BITS)) as uint) & ((1 << w) - 1)
+    }
+
+    fn swap_rows(&mut self, a: uint, b: uint) {
+        let s = self.stride;
+        let d = self.data.as_mut_slice();
+        for t in range(0, s) {
+            d.swap(a * s + t, b * s + t);
+        }
+    }
+
+    // row dst ^= row src, for the words from `from` onward.
+    fn xor_row(&mut self, dst: uint, src: uint, from: uint) {
+        let s = self.stride;
+        let d = self.data.as_mut_slice();
+        for t in range(from, s) {
+            let x = d[src * s + t];
+            d[dst * s + t] ^= x;
+        }
+    }
+
+    // row dst |= row src.
+    fn or_row(&mut self, dst: uint, src: uint) {
+        let s = self.stride;
+        let d = self.data.as_mut_slice();
+        for t in range(0, s) {
+            let x = d[src * s + t];
+            d[dst * s + t] |= x;
+        }
+    }
+}
+
+impl BasicMatrix<bool> for BitMatrix {
+    #[inline(always)]
+    fn get(&self, i: uint, j: uint) -> bool {
+        if i < self.rows && j < self.cols {
+            let word = self.data.as_slice()[i * self.stride + j / WORD_BITS];
+            (word >> (j % WORD_BITS)) & 1 == 1
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (self.rows, self.cols)))
+        }
+    }
+
+    #[inline(always)]
+    fn set(&mut self, i: uint, j: uint, x: bool) {
+        if i < self.rows && j < self.cols {
+            let k = i * self.stride + j / WORD_BITS;
+            let bit = 1u64 << (j % WORD_BITS);
+            let word = self.data.as_mut_slice();
+            if x { word[k] |= bit } else { word[k] &= !bit }
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (self.rows, self.cols)))
+        }
+    }
+
+    fn num_rows(&self) -> uint { self.rows }
+    fn num_cols(&self) -> uint { self.cols }
+}
+
+impl Create<bool> for BitMatrix {
+    fn create(i: uint, j: uint, init: |uint, uint| -> bool) -> BitMatrix {
+        let mut m = BitMatrix::new(i, j);
+        for r in range(0, i) {
+            for c in range(0, j) {
+                if init(r, c) {
+                    m.set(r, c, true);
+                }
+            }
+        }
+        m
+    }
+}
+
+// The Method of Four Russians. The rows of B are taken eight at a
+// time, and a table of all 256 sums of those eight rows is built with
+// one row operation per entry. Each row of A then picks its entry from
+// the table with the matching eight bits, so a group costs one row
+// operation per row of A instead of up to eight.
+fn four_russians(A: &BitMatrix, B: &BitMatrix, xor: bool) -> BitMatrix {
+    static GROUP: uint = 8;
+
+    if A.cols != B.rows {
+        fail!(format!("Incompatible matrix sizes. LHS: {:?}, RHS: {:?}",
+                   (A.rows, A.cols),
+                   (B.rows, B.cols)))
+    }
+
+    let s = B.stride;
+    let mut C = BitMatrix::new(A.rows, B.cols);
+    let mut table: Vec<u64> = Vec::from_elem((1 << GROUP) * s, 0u64);
+
+    let mut g = 0;
+    while g < A.cols {
+        let w = cmp::min(GROUP, A.cols - g);
+
+        // Entry 2^t + e is entry e combined with row g + t of B.
+        {
+            let tab = table.as_mut_slice();
+            let b = B.data.as_slice();
+            for t in range(0, w) {
+                let row = (g + t) * s;
+                for e in range(0u, 1 << t) {
+                    let dst = ((1 << t) + e) * s;
+                    for u in range(0, s) {
+                        let x = tab[e * s + u];
+                        tab[dst + u] = if xor { x ^ b[row + u] } else { x | b[row + u] };
+                    }
+                }
+            }
+        }
+
+        let tab = table.as_slice();
+        let c = C.data.as_mut_slice();
+        for i in range(0, A.rows) {
+            let e = A.bits(i, g, w);
+            if e != 0 {
+                for u in range(0, s) {
+                    let x = tab[e * s + u];
+                    if xor { c[i * s + u] ^= x } else { c[i * s + u] |= x }
+                }
+            }
+        }
+
+        g += w;
+    }
+    C
+}
+
+// The Boolean product, where C(i, j) is true if A(i, k) and B(k, j)
+// are both true for some k.
+pub fn bool_mat_mul(A: &BitMatrix, B: &BitMatrix) -> BitMatrix {
+    four_russians(A, B, false)
+}
+
+// The product over GF(2), with addition modulo 2.
+pub fn gf2_mat_mul(A: &BitMatrix, B: &BitMatrix) -> BitMatrix {
+    four_russians(A, B, true)
+}
+
+// Gauss-Jordan elimination over GF(2), in place. A is left in reduced
+// row echelon form and the pivot columns are returned, so their count
+// is the rank.
+pub fn gf2_eliminate(A: &mut BitMatrix) -> Vec<uint> {
+    let mut pivots = Vec::new();
+    let mut r = 0;
+    for c in range(0, A.cols) {
+        if r == A.rows {
+            break
+        }
+
+        let w = c / WORD_BITS;
+        let bit = 1u64 << (c % WORD_BITS);
+        let p = match range(r, A.rows).find(|&i| A.data.as_slice()[i * A.stride + w] & bit != 0) {
+            Some(p) => p,
+            None => continue
+        };
+        A.swap_rows(p, r);
+
+        // Words before w are zero in the pivot row.
+        for i in range(0, A.rows) {
+            if i != r && A.data.as_slice()[i * A.stride + w] & bit != 0 {
+                A.xor_row(i, r, w);
+            }
+        }
+        pivots.push(c);
+        r += 1;
+    }
+    pivots
+}
+
+// The rank over GF(2).
+pub fn gf2_rank(A: &BitMatrix) -> uint {
+    let mut R = A.clone();
+    gf2_eliminate(&mut R).len()
+}
+
+// The transitive closure of the relation A, by Warshall's algorithm
+// with whole rows combined at once. (i, j) is set in the result if
+// there is a path of one or more steps from i to j; OR with the
+// identity for the reflexive closure.
+pub fn transitive_closure(A: &BitMatrix) -> BitMatrix {
+    assert!(A.rows == A.cols);
+
+    let mut R = A.clone();
+    for k in range(0, R.rows) {
+        for i in range(0, R.rows) {
+            if i != k && R.get(i, k) {
+                R.or_row(i, k);
+            }
+        }
+    }
+    R
+}
+
+#[cfg(test)]
+mod test {
+    use matrix::{BasicMatrix, Create};
+    use super::{BitMatrix, bool_mat_mul, gf2_mat_mul, gf2_eliminate};
+
+    // A reproducible pseudo-random matrix, from a xorshift generator.
+    fn random(rows: uint, cols: uint, seed: u64) -> BitMatrix {
+        let mut x = seed;
+        Create::create(rows, cols, |_, _| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x & 1 == 1
+        })
+    }
+
+    fn naive_mul(A: &BitMatrix, B: &BitMatrix, xor: bool) -> BitMatrix {
+        Create::create(A.num_rows(), B.num_cols(), |i, j| {
+            let mut acc = false;
+            for k in range(0, A.num_cols()) {
+                let p = A.get(i, k) && B.get(k, j);
+                acc = if xor { acc != p } else { acc || p };
+            }
+            acc
+        })
+    }
+
+    // Gauss-Jordan elimination one entry at a time.
+    fn naive_eliminate(A: &mut BitMatrix) -> Vec<uint> {
+        let (m, n) = (A.num_rows(), A.num_cols());
+        let mut pivots = Vec::new();
+        let mut r = 0;
+        for c in range(0, n) {
+            if r == m {
+                break
+            }
+            let p = match range(r, m).find(|&i| A.get(i, c)) {
+                Some(p) => p,
+                None => continue
+            };
+            for j in range(0, n) {
+                let (x, y) = (A.get(r, j), A.get(p, j));
+                A.set(r, j, y);
+                A.set(p, j, x);
+            }
+            for i in range(0, m) {
+                if i != r && A.get(i, c) {
+                    for j in range(0, n) {
+                        let x = A.get(i, j) != A.get(r, j);
+                        A.set(i, j, x);
+                    }
+                }
+            }
+            pivots.push(c);
+            r += 1;
+        }
+        pivots
+    }
+
+    // Inner dimensions that leave a short last group of eight rows of
+    // B, and output widths that leave a partial last word.
+    static SHAPES: [(uint, uint, uint), ..4] = [(1, 1, 1), (13, 77, 70), (9, 64, 129), (5, 130, 65)];
+
+    #[test]
+    fn test_bool_mat_mul() {
+        for (k, &(m, l, n)) in SHAPES.iter().enumerate() {
+            let A = random(m, l, 1 + k as u64);
+            let B = random(l, n, 101 + k as u64);
+            assert_eq!(bool_mat_mul(&A, &B), naive_mul(&A, &B, false));
+        }
+    }
+
+    #[test]
+    fn test_gf2_mat_mul() {
+        for (k, &(m, l, n)) in SHAPES.iter().enumerate() {
+            let A = random(m, l, 7 + k as u64);
+            let B = random(l, n, 107 + k as u64);
+            assert_eq!(gf2_mat_mul(&A, &B), naive_mul(&A, &B, true));
+        }
+    }
+
+    #[test]
+    fn test_gf2_eliminate() {
+        for &(m, n) in [(20u, 70u), (70, 130), (3, 5)].iter() {
+            let mut A = random(m, n, (m * n) as u64);
+            let mut E = A.clone();
+            let pivots = naive_eliminate(&mut E);
+            assert_eq!(gf2_eliminate(&mut A), pivots);
+            assert_eq!(A, E);
+        }
+    }
+
+    // Repeated rows leave the rank short, so some columns past the
+    // first word have no pivot.
+    #[test]
+    fn test_gf2_eliminate_rank_deficient() {
+        let R = random(10, 100, 42);
+        let mut A: BitMatrix = Create::create(30, 100, |i, j| R.get(i % 10, j));
+        let mut E = A.clone();
+        let pivots = naive_eliminate(&mut E);
+        assert!(pivots.len() <= 10);
+        assert_eq!(gf2_eliminate(&mut A), pivots);
+        assert_eq!(A, E);
+    }
+}
@@ -0,0 +1,245 @@
+// Diagonal and block diagonal matrices.
+//
+// Both report their blocks through BasicMatrix::diagonal_blocks, so the
+// generic mat_mul, inverse and determinant in algorithms work block by
+// block on them. The functions below call the block code directly, and
+// the inverses and factorizations among them keep the structured type.
+
+#![allow(uppercase_variables)]
+
+use matrix::{BasicMatrix, Create, Semiring, Field, RealField};
+use matrix::algorithms;
+use matrix::triangular::LowerTriangular;
+
+// A square matrix that stores only its diagonal.
+#[deriving(Clone)]
+pub struct Diagonal<T> {
+    data: Vec<T>
+}
+
+impl<T: Semiring> Diagonal<T> {
+    /// Creates a diagonal matrix with the given diagonal entries.
+    pub fn from_vec(d: Vec<T>) -> Diagonal<T> {
+        Diagonal { data: d }
+    }
+
+    pub fn diag<'a>(&'a self) -> &'a [T] { self.data.as_slice() }
+}
+
+impl<T: Semiring> BasicMatrix<T> for Diagonal<T> {
+    #[inline(always)]
+    fn get(&self, i: uint, j: uint) -> T {
+        let n = self.data.len();
+        if i < n && j < n {
+            if i == j { self.data.as_slice()[i].clone() } else { Semiring::zero() }
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (n, n)))
+        }
+    }
+
+    #[inline(always)]
+    fn set(&mut self, i: uint, j: uint, x: T) {
+        let n = self.data.len();
+        if i < n && j < n {
+            if i == j {
+                self.data.as_mut_slice()[i] = x
+            }
+            else if !x.is_zero() {
+                fail!(format!("Cannot set {:?} off the diagonal.", (i, j)))
+            }
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (n, n)))
+        }
+    }
+
+    fn num_rows(&self) -> uint { self.data.len() }
+    fn num_cols(&self) -> uint { self.data.len() }
+
+    fn diagonal_blocks(&self) -> Option<Vec<uint>> {
+        Some(range(0, self.data.len() + 1).collect())
+    }
+}
+
+// Only the diagonal of `init` is evaluated.
+impl<T: Semiring> Create<T> for Diagonal<T> {
+    fn create(i: uint, j: uint, init: |uint, uint| -> T) -> Diagonal<T> {
+        assert!(i == j);
+        Diagonal { data: Vec::from_fn(i, |k| init(k, k)) }
+    }
+}
+
+// D A, which scales row i of A by D(i, i).
+pub fn diag_mat_mul<T: Semiring, M: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>
+(D: &Diagonal<T>, A: &M) -> R
+{
+    assert!(D.num_cols() == A.num_rows());
+    Create::<T>::create(A.num_rows(), A.num_cols(), |i, j| {
+        *D.data.get(i) * A.get(i, j)
+    })
+}
+
+// A D, which scales column j of A by D(j, j).
+pub fn mat_diag_mul<T: Semiring, M: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>
+(A: &M, D: &Diagonal<T>) -> R
+{
+    assert!(A.num_cols() == D.num_rows());
+    Create::<T>::create(A.num_rows(), A.num_cols(), |i, j| {
+        A.get(i, j) * *D.data.get(j)
+    })
+}
+
+pub fn diag_inverse<T: Field>(D: &Diagonal<T>) -> Diagonal<T> {
+    let one: T = Semiring::one();
+    Diagonal { data: D.data.iter().map(|d| one / *d).collect() }
+}
+
+pub fn diag_determinant<T: Semiring>(D: &Diagonal<T>) -> T {
+    D.data.iter().fold(Semiring::one(), |acc: T, d| acc * *d)
+}
+
+pub fn diag_cholesky<T: RealField>(D: &Diagonal<T>) -> Diagonal<T> {
+    Diagonal { data: D.data.iter().map(|d| d.sqrt()).collect() }
+}
+
+// A square matrix made of square blocks along the diagonal. Block k
+// covers rows and columns offsets[k] to offsets[k + 1].
+#[deriving(Clone)]
+pub struct BlockDiagonal<T, M> {
+    offsets: Vec<uint>,
+    blocks: Vec<M>
+}
+
+impl<T: Semiring, M: BasicMatrix<T>> BlockDiagonal<T, M> {
+    pub fn from_blocks(blocks: Vec<M>) -> BlockDiagonal<T, M> {
+        let mut offsets = Vec::with_capacity(blocks.len() + 1);
+        offsets.push(0);
+        for b in blocks.iter() {
+            assert!(b.num_rows() == b.num_cols());
+            let last = *offsets.get(offsets.len() - 1);
+            offsets.push(last + b.num_rows());
+        }
+        BlockDiagonal { offsets: offsets, blocks: blocks }
+    }
+
+    pub fn blocks<'a>(&'a self) -> &'a [M] { self.blocks.as_slice() }
+
+    // The block holding row or column i.
+    fn block_of(&self, i: uint) -> uint {
+        algorithms::block_of(self.offsets.as_slice(), i)
+    }
+}
+
+impl<T: Semiring, M: BasicMatrix<T>> BasicMatrix<T> for BlockDiagonal<T, M> {
+    fn get(&self, i: uint, j: uint) -> T {
+        let n = self.num_rows();
+        if i < n && j < n {
+            let k = self.block_of(i);
+            let o = *self.offsets.get(k);
+            if j >= o && j < *self.offsets.get(k + 1) {
+                self.blocks.get(k).get(i - o, j - o)
+            }
+            else {
+                Semiring::zero()
+            }
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (n, n)))
+        }
+    }
+
+    fn set(&mut self, i: uint, j: uint, x: T) {
+        let n = self.num_rows();
+        if i < n && j < n {
+            let k = self.block_of(i);
+            let o = *self.offsets.get(k);
+            if j >= o && j < *self.offsets.get(k + 1) {
+                self.blocks.get_mut(k).set(i - o, j - o, x)
+            }
+            else if !x.is_zero() {
+                fail!(format!("Cannot set {:?} outside the diagonal blocks.",
+                              (i, j)))
+            }
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (n, n)))
+        }
+    }
+
+    fn num_rows(&self) -> uint { *self.offsets.get(self.blocks.len()) }
+    fn num_cols(&self) -> uint { *self.offsets.get(self.blocks.len()) }
+
+    // Empty blocks are left out, so every reported block has a row.
+    fn diagonal_blocks(&self) -> Option<Vec<uint>> {
+        let mut offsets = self.offsets.clone();
+        offsets.dedup();
+        Some(offsets)
+    }
+}
+
+// B A, computed block row by block row.
+pub fn block_mat_mul<T: Semiring, M: BasicMatrix<T>, RHS: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>
+(B: &BlockDiagonal<T, M>, A: &RHS) -> R
+{
+    assert!(B.num_cols() == A.num_rows());
+    Create::<T>::create(A.num_rows(), A.num_cols(), |i, j| {
+        let k = B.block_of(i);
+        let o = *B.offsets.get(k);
+        let block = B.blocks.get(k);
+        let mut acc: T = Semiring::zero();
+        for l in range(0, block.num_cols()) {
+            acc = acc + block.get(i - o, l) * A.get(o + l, j)
+        }
+        acc
+    })
+}
+
+// A B, computed block column by block column.
+pub fn mat_block_mul<T: Semiring, LHS: BasicMatrix<T>, M: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>
+(A: &LHS, B: &BlockDiagonal<T, M>) -> R
+{
+    assert!(A.num_cols() == B.num_rows());
+    Create::<T>::create(A.num_rows(), A.num_cols(), |i, j| {
+        let k = B.block_of(j);
+        let o = *B.offsets.get(k);
+        let block = B.blocks.get(k);
+        let mut acc: T = Semiring::zero();
+        for l in range(0, block.num_rows()) {
+            acc = acc + A.get(i, o + l) * block.get(l, j - o)
+        }
+        acc
+    })
+}
+
+// Inverts each block.
+pub fn block_inverse<T: Field, M: BasicMatrix<T> + Create<T>>
+(B: &BlockDiagonal<T, M>) -> BlockDiagonal<T, M>
+{
+    BlockDiagonal {
+        offsets: B.offsets.clone(),
+        blocks: B.blocks.iter().map(|b| algorithms::inverse(b)).collect()
+    }
+}
+
+pub fn block_determinant<T: Field, M: BasicMatrix<T>>(B: &BlockDiagonal<T, M>) -> T {
+    B.blocks.iter().fold(Semiring::one(), |acc: T, b| acc * algorithms::determinant(b))
+}
+
+// Factors each block with cholesky_blocked.
+pub fn block_cholesky<T: RealField, M: BasicMatrix<T>>(B: &BlockDiagonal<T, M>)
+    -> BlockDiagonal<T, LowerTriangular<T>>
+{
+    BlockDiagonal {
+        offsets: B.offsets.clone(),
+        blocks: B.blocks.iter().map(|b| algorithms::cholesky_blocked(b)).collect()
+    }
+}
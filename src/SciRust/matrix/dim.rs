@@ -0,0 +1,142 @@
+// Matrices whose dimensions are part of their type, so that shape
+// mismatches in products, sums and concatenations are caught by the
+// type checker. The one check left to run time is that the size named
+// for a concatenation is the sum of its parts.
+//
+// Rust has no integer type parameters, so dimensions are marker types
+// implementing Dim. D1 through D16 are provided; other sizes can be
+// declared the same way:
+//
+//     pub struct D1200;
+//     impl Dim for D1200 { fn dim(_: Option<D1200>) -> uint { 1200 } }
+
+use matrix::{BasicMatrix, Create, Matrix, Semiring, Ring, Field};
+use matrix::algorithms;
+
+pub trait Dim {
+    /// The size this type stands for. The argument only names the
+    /// type and is always None.
+    fn dim(_: Option<Self>) -> uint;
+}
+
+#[inline(always)]
+pub fn dim_of<N: Dim>() -> uint {
+    Dim::dim(None::<N>)
+}
+
+macro_rules! dims(
+    ($($name:ident = $n:expr),+) => (
+        $(
+            #[deriving(Clone)]
+            pub struct $name;
+            impl Dim for $name {
+                #[inline(always)]
+                fn dim(_: Option<$name>) -> uint { $n }
+            }
+        )+
+    )
+)
+
+dims!(D1 = 1, D2 = 2, D3 = 3, D4 = 4, D5 = 5, D6 = 6, D7 = 7, D8 = 8,
+      D9 = 9, D10 = 10, D11 = 11, D12 = 12, D13 = 13, D14 = 14, D15 = 15,
+      D16 = 16)
+
+// An R x C matrix backed by a Matrix<T>.
+#[deriving(Clone)]
+pub struct StaticMatrix<T, R, C> {
+    data: Matrix<T>
+}
+
+impl<T: Clone, R: Dim, C: Dim> StaticMatrix<T, R, C> {
+    /// Wraps a dynamic matrix, or returns None if its shape does not
+    /// match R x C.
+    pub fn from_matrix(m: Matrix<T>) -> Option<StaticMatrix<T, R, C>> {
+        if m.num_rows() == dim_of::<R>() && m.num_cols() == dim_of::<C>() {
+            Some(StaticMatrix { data: m })
+        }
+        else {
+            None
+        }
+    }
+
+    pub fn as_matrix<'a>(&'a self) -> &'a Matrix<T> { &self.data }
+    pub fn unwrap(self) -> Matrix<T> { self.data }
+}
+
+impl<T: Clone, R: Dim, C: Dim> BasicMatrix<T> for StaticMatrix<T, R, C> {
+    #[inline(always)]
+    fn get(&self, i: uint, j: uint) -> T { self.data.get(i, j) }
+    #[inline(always)]
+    fn set(&mut self, i: uint, j: uint, x: T) { self.data.set(i, j, x) }
+
+    #[inline(always)]
+    fn num_rows(&self) -> uint { dim_of::<R>() }
+    #[inline(always)]
+    fn num_cols(&self) -> uint { dim_of::<C>() }
+
+    fn row_major_data<'a>(&'a self) -> Option<&'a [T]> {
+        self.data.row_major_data()
+    }
+}
+
+// Generic code creating a StaticMatrix must ask for exactly R x C.
+impl<T: Clone, R: Dim, C: Dim> Create<T> for StaticMatrix<T, R, C> {
+    fn create(i: uint, j: uint, init: |uint, uint| -> T) -> StaticMatrix<T, R, C> {
+        if i != dim_of::<R>() || j != dim_of::<C>() {
+            fail!(format!("Cannot create a {:?} matrix with static size {:?}.",
+                          (i, j), (dim_of::<R>(), dim_of::<C>())))
+        }
+        StaticMatrix { data: Create::create(i, j, init) }
+    }
+}
+
+pub fn mat_mul<T: Semiring, R: Dim, K: Dim, C: Dim>
+(lhs: &StaticMatrix<T, R, K>, rhs: &StaticMatrix<T, K, C>) -> StaticMatrix<T, R, C>
+{
+    StaticMatrix { data: algorithms::mat_mul(&lhs.data, &rhs.data) }
+}
+
+pub fn mat_add<T: Semiring, R: Dim, C: Dim>
+(lhs: &StaticMatrix<T, R, C>, rhs: &StaticMatrix<T, R, C>) -> StaticMatrix<T, R, C>
+{
+    StaticMatrix { data: algorithms::mat_add(&lhs.data, &rhs.data) }
+}
+
+pub fn mat_sub<T: Ring, R: Dim, C: Dim>
+(lhs: &StaticMatrix<T, R, C>, rhs: &StaticMatrix<T, R, C>) -> StaticMatrix<T, R, C>
+{
+    StaticMatrix { data: algorithms::mat_sub(&lhs.data, &rhs.data) }
+}
+
+pub fn transpose<T: Clone, R: Dim, C: Dim>(m: &StaticMatrix<T, R, C>) -> StaticMatrix<T, C, R> {
+    StaticMatrix { data: algorithms::transpose(&m.data) }
+}
+
+pub fn inverse<T: Field, N: Dim>(m: &StaticMatrix<T, N, N>) -> StaticMatrix<T, N, N> {
+    StaticMatrix { data: algorithms::inverse(&m.data) }
+}
+
+// Side by side. The shared row count is checked by the type checker,
+// but marker types cannot be added, so the caller names the output
+// width C and it is checked against C1 + C2 when this runs.
+pub fn concat_cols<T: Clone, R: Dim, C1: Dim, C2: Dim, C: Dim>
+(lhs: &StaticMatrix<T, R, C1>, rhs: &StaticMatrix<T, R, C2>) -> StaticMatrix<T, R, C>
+{
+    if dim_of::<C>() != dim_of::<C1>() + dim_of::<C2>() {
+        fail!(format!("Cannot concatenate {} and {} columns into {}.",
+                      dim_of::<C1>(), dim_of::<C2>(), dim_of::<C>()))
+    }
+    StaticMatrix { data: algorithms::concat_cols(&lhs.data, &rhs.data) }
+}
+
+// One above the other, with the output height R checked against
+// R1 + R2 as in concat_cols.
+pub fn concat_rows<T: Clone, R1: Dim, R2: Dim, R: Dim, C: Dim>
+(top: &StaticMatrix<T, R1, C>, bottom: &StaticMatrix<T, R2, C>) -> StaticMatrix<T, R, C>
+{
+    if dim_of::<R>() != dim_of::<R1>() + dim_of::<R2>() {
+        fail!(format!("Cannot concatenate {} and {} rows into {}.",
+                      dim_of::<R1>(), dim_of::<R2>(), dim_of::<R>()))
+    }
+    StaticMatrix { data: algorithms::concat_rows(&top.data, &bottom.data) }
+}
@@ -0,0 +1,239 @@
+// Small fixed-size matrices and vectors stored inline in arrays.
+//
+// Each size is its own type, stamped out by the macros below. The loop
+// bounds in the shared code are constants, so they unroll completely;
+// determinant and inverse are written out by hand for each size.
+
+#![allow(uppercase_variables)]
+
+use std::ops;
+
+use matrix::{BasicMatrix, Create, Vector, Semiring, Ring, Field};
+
+macro_rules! fixed_vector(
+    ($name:ident, $n:expr) => (
+        #[deriving(Clone, PartialEq, Show)]
+        pub struct $name<T> {
+            pub data: [T, ..$n]
+        }
+
+        impl<T: Copy> Vector<T> for $name<T> {
+            fn len(&self) -> uint { $n }
+            #[inline(always)]
+            fn get(&self, i: uint) -> T { self.data[i] }
+            #[inline(always)]
+            fn set(&mut self, i: uint, x: T) { self.data[i] = x }
+        }
+
+        impl<T: Copy> ops::Index<uint, T> for $name<T> {
+            #[inline(always)]
+            fn index(&self, i: &uint) -> T { self.data[*i] }
+        }
+
+        impl<T: Semiring + Copy> $name<T> {
+            pub fn from_fn(f: |uint| -> T) -> $name<T> {
+                let mut data = [Semiring::zero(), ..$n];
+                for i in range(0u, $n) {
+                    data[i] = f(i);
+                }
+                $name { data: data }
+            }
+
+            pub fn dot(&self, other: &$name<T>) -> T {
+                let mut acc: T = Semiring::zero();
+                for i in range(0u, $n) {
+                    acc = acc + self.data[i] * other.data[i];
+                }
+                acc
+            }
+        }
+    )
+)
+
+macro_rules! fixed_matrix(
+    ($name:ident, $vec:ident, $n:expr) => (
+        // Entries are stored in row-major order.
+        #[deriving(Clone, PartialEq, Show)]
+        pub struct $name<T> {
+            pub data: [T, ..$n * $n]
+        }
+
+        impl<T: Copy> BasicMatrix<T> for $name<T> {
+            #[inline(always)]
+            fn get(&self, i: uint, j: uint) -> T {
+                assert!(i < $n && j < $n);
+                self.data[i * $n + j]
+            }
+
+            #[inline(always)]
+            fn set(&mut self, i: uint, j: uint, x: T) {
+                assert!(i < $n && j < $n);
+                self.data[i * $n + j] = x
+            }
+
+            fn num_rows(&self) -> uint { $n }
+            fn num_cols(&self) -> uint { $n }
+
+            fn row_major_data<'a>(&'a self) -> Option<&'a [T]> {
+                Some(self.data.as_slice())
+            }
+        }
+
+        impl<T: Semiring + Copy> Create<T> for $name<T> {
+            fn create(i: uint, j: uint, init: |uint, uint| -> T) -> $name<T> {
+                assert!(i == $n && j == $n);
+                let mut data = [Semiring::zero(), ..$n * $n];
+                for k in range(0u, $n * $n) {
+                    data[k] = init(k / $n, k % $n);
+                }
+                $name { data: data }
+            }
+        }
+
+        impl<T: Semiring + Copy> ops::Mul<$name<T>, $name<T>> for $name<T> {
+            fn mul(&self, rhs: &$name<T>) -> $name<T> {
+                let mut data = [Semiring::zero(), ..$n * $n];
+                for i in range(0u, $n) {
+                    for j in range(0u, $n) {
+                        let mut acc: T = Semiring::zero();
+                        for k in range(0u, $n) {
+                            acc = acc + self.data[i * $n + k] * rhs.data[k * $n + j];
+                        }
+                        data[i * $n + j] = acc;
+                    }
+                }
+                $name { data: data }
+            }
+        }
+
+        impl<T: Semiring + Copy> $name<T> {
+            pub fn identity() -> $name<T> {
+                Create::create($n, $n, |i, j| {
+                    if i == j { Semiring::one() } else { Semiring::zero() }
+                })
+            }
+
+            pub fn transpose(&self) -> $name<T> {
+                Create::create($n, $n, |i, j| self.data[j * $n + i])
+            }
+
+            pub fn mul_vec(&self, x: &$vec<T>) -> $vec<T> {
+                $vec::from_fn(|i| {
+                    let mut acc: T = Semiring::zero();
+                    for k in range(0u, $n) {
+                        acc = acc + self.data[i * $n + k] * x.data[k];
+                    }
+                    acc
+                })
+            }
+        }
+    )
+)
+
+fixed_vector!(Vector2, 2)
+fixed_vector!(Vector3, 3)
+fixed_vector!(Vector4, 4)
+
+fixed_matrix!(Matrix2, Vector2, 2)
+fixed_matrix!(Matrix3, Vector3, 3)
+fixed_matrix!(Matrix4, Vector4, 4)
+
+impl<T: Ring + Copy> Matrix2<T> {
+    pub fn determinant(&self) -> T {
+        let m = &self.data;
+        m[0] * m[3] - m[1] * m[2]
+    }
+}
+
+impl<T: Field + Copy> Matrix2<T> {
+    pub fn inverse(&self) -> Matrix2<T> {
+        let m = &self.data;
+        let d = self.determinant();
+        Matrix2 { data: [ m[3] / d, -m[1] / d,
+                         -m[2] / d,  m[0] / d] }
+    }
+}
+
+impl<T: Ring + Copy> Matrix3<T> {
+    pub fn determinant(&self) -> T {
+        let m = &self.data;
+        m[0] * (m[4] * m[8] - m[5] * m[7])
+            + m[1] * (m[5] * m[6] - m[3] * m[8])
+            + m[2] * (m[3] * m[7] - m[4] * m[6])
+    }
+}
+
+impl<T: Field + Copy> Matrix3<T> {
+    // The adjugate divided by the determinant.
+    pub fn inverse(&self) -> Matrix3<T> {
+        let m = &self.data;
+        let d = self.determinant();
+        Matrix3 { data: [
+            (m[4] * m[8] - m[5] * m[7]) / d,
+            (m[2] * m[7] - m[1] * m[8]) / d,
+            (m[1] * m[5] - m[2] * m[4]) / d,
+            (m[5] * m[6] - m[3] * m[8]) / d,
+            (m[0] * m[8] - m[2] * m[6]) / d,
+            (m[2] * m[3] - m[0] * m[5]) / d,
+            (m[3] * m[7] - m[4] * m[6]) / d,
+            (m[1] * m[6] - m[0] * m[7]) / d,
+            (m[0] * m[4] - m[1] * m[3]) / d ] }
+    }
+}
+
+impl<T: Ring + Copy> Matrix4<T> {
+    // The 2x2 minors of the top two rows (s) and the bottom two rows
+    // (c), from which both the determinant and the adjugate follow by
+    // Laplace expansion.
+    fn minors(&self) -> ([T, ..6], [T, ..6]) {
+        let a = &self.data;
+        let s = [a[0] * a[5] - a[4] * a[1],
+                 a[0] * a[6] - a[4] * a[2],
+                 a[0] * a[7] - a[4] * a[3],
+                 a[1] * a[6] - a[5] * a[2],
+                 a[1] * a[7] - a[5] * a[3],
+                 a[2] * a[7] - a[6] * a[3]];
+        let c = [a[8] * a[13] - a[12] * a[9],
+                 a[8] * a[14] - a[12] * a[10],
+                 a[8] * a[15] - a[12] * a[11],
+                 a[9] * a[14] - a[13] * a[10],
+                 a[9] * a[15] - a[13] * a[11],
+                 a[10] * a[15] - a[14] * a[11]];
+        (s, c)
+    }
+
+    pub fn determinant(&self) -> T {
+        let (s, c) = self.minors();
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3]
+            + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+}
+
+impl<T: Field + Copy> Matrix4<T> {
+    pub fn inverse(&self) -> Matrix4<T> {
+        let a = &self.data;
+        let (s, c) = self.minors();
+        let d = s[0] * c[5] - s[1] * c[4] + s[2] * c[3]
+            + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+        Matrix4 { data: [
+            ( a[5] * c[5] - a[6] * c[4] + a[7] * c[3]) / d,
+            (-a[1] * c[5] + a[2] * c[4] - a[3] * c[3]) / d,
+            ( a[13] * s[5] - a[14] * s[4] + a[15] * s[3]) / d,
+            (-a[9] * s[5] + a[10] * s[4] - a[11] * s[3]) / d,
+
+            (-a[4] * c[5] + a[6] * c[2] - a[7] * c[1]) / d,
+            ( a[0] * c[5] - a[2] * c[2] + a[3] * c[1]) / d,
+            (-a[12] * s[5] + a[14] * s[2] - a[15] * s[1]) / d,
+            ( a[8] * s[5] - a[10] * s[2] + a[11] * s[1]) / d,
+
+            ( a[4] * c[4] - a[5] * c[2] + a[7] * c[0]) / d,
+            (-a[0] * c[4] + a[1] * c[2] - a[3] * c[0]) / d,
+            ( a[12] * s[4] - a[13] * s[2] + a[15] * s[0]) / d,
+            (-a[8] * s[4] + a[9] * s[2] - a[11] * s[0]) / d,
+
+            (-a[4] * c[3] + a[5] * c[1] - a[6] * c[0]) / d,
+            ( a[0] * c[3] - a[1] * c[1] + a[2] * c[0]) / d,
+            (-a[12] * s[3] + a[13] * s[1] - a[14] * s[0]) / d,
+            ( a[8] * s[3] - a[9] * s[1] + a[10] * s[0]) / d ] }
+    }
+}
@@ -5,17 +5,17 @@ use std::num::from_int;
 use std::rand;
 use rand::Rand;
 
-use matrix::{BasicMatrix, Create, Ring};
+use matrix::{BasicMatrix, Create, Semiring};
 
-pub fn identity<T: Ring, M: BasicMatrix<T> + Create<T>>(N: uint)
+pub fn identity<T: Semiring, M: BasicMatrix<T> + Create<T>>(N: uint)
     -> M
 {
     Create::<T>::create(N, N, |i, j| {
         if i == j {
-            Ring::one()
+            Semiring::one()
         }
         else {
-            Ring::zero()
+            Semiring::zero()
         }
     })
 }
@@ -38,8 +38,8 @@ pub fn rand_L1<T: Rand + FromPrimitive, M: BasicMatrix<T> + Create<T>>(N: uint)
     })
 }
 
-pub fn zero_matrix<T: Ring, M: BasicMatrix<T> + Create<T>>
+pub fn zero_matrix<T: Semiring, M: BasicMatrix<T> + Create<T>>
     (n: uint, m: uint) -> M
 {
-    Create::<T>::create(n, m, |_i, _j| Ring::zero())
+    Create::<T>::create(n, m, |_i, _j| Semiring::zero())
 }
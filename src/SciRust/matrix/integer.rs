@@ -0,0 +1,298 @@
+// Exact algorithms for integer matrices: the Bareiss determinant and
+// the Hermite and Smith normal forms.
+//
+// These work over any EuclideanRing, which covers the machine integers
+// and BigInt. Intermediate entries can grow well past the input, so
+// over i64 every operation is checked. The checked_ functions return
+// None on overflow, so that the caller can retry over BigInt; the
+// others fail, which ends the task.
+
+#![allow(uppercase_variables)]
+
+use matrix::{BasicMatrix, Create, Matrix, Semiring};
+use matrix::algorithms::{convert, swap_rows};
+use matrix::generate::identity;
+use scalar::EuclideanRing;
+
+// Unwraps the result of an _exact operation, returning None from the
+// enclosing function on overflow.
+macro_rules! exact(
+    ($e:expr) => (
+        match $e {
+            Some(x) => x,
+            None => return None
+        }
+    )
+)
+
+fn no_overflow<X>(x: Option<X>) -> X {
+    match x {
+        Some(x) => x,
+        None => fail!("Integer overflow. Use the checked_ version, or BigInt.")
+    }
+}
+
+fn swap_cols<T, M: BasicMatrix<T>>(A: &mut M, a: uint, b: uint) {
+    for i in range(0, A.num_rows()) {
+        let x = A.get(i, a);
+        let y = A.get(i, b);
+        A.set(i, a, y);
+        A.set(i, b, x);
+    }
+}
+
+// row dst -= q * row src.
+fn sub_row<T: EuclideanRing, M: BasicMatrix<T>>(A: &mut M, dst: uint, src: uint, q: &T) -> Option<()> {
+    for j in range(0, A.num_cols()) {
+        let x = exact!(A.get(dst, j).sub_exact(&exact!(q.mul_exact(&A.get(src, j)))));
+        A.set(dst, j, x);
+    }
+    Some(())
+}
+
+// col dst -= q * col src.
+fn sub_col<T: EuclideanRing, M: BasicMatrix<T>>(A: &mut M, dst: uint, src: uint, q: &T) -> Option<()> {
+    for i in range(0, A.num_rows()) {
+        let x = exact!(A.get(i, dst).sub_exact(&exact!(q.mul_exact(&A.get(i, src)))));
+        A.set(i, dst, x);
+    }
+    Some(())
+}
+
+fn negate_row<T: EuclideanRing, M: BasicMatrix<T>>(A: &mut M, i: uint) -> Option<()> {
+    for j in range(0, A.num_cols()) {
+        let x = exact!(A.get(i, j).neg_exact());
+        A.set(i, j, x);
+    }
+    Some(())
+}
+
+fn abs<T: EuclideanRing>(x: &T) -> Option<T> {
+    let zero: T = Semiring::zero();
+    if *x < zero { x.neg_exact() } else { Some(x.clone()) }
+}
+
+// The quotient rounded toward minus infinity, so that the remainder
+// a - q b has the sign of b.
+fn div_floor<T: EuclideanRing>(a: &T, b: &T) -> Option<T> {
+    let zero: T = Semiring::zero();
+    let one: T = Semiring::one();
+    let (q, r) = exact!(a.div_rem_exact(b));
+    if !r.is_zero() && ((r < zero) != (*b < zero)) { q.sub_exact(&one) } else { Some(q) }
+}
+
+// The determinant by fraction-free Gaussian elimination. Every division
+// is exact, and each intermediate entry is a minor of A, so the entries
+// stay no larger than Hadamard's bound on the determinant. Fails on
+// overflow.
+pub fn bareiss_determinant<T: EuclideanRing, M: BasicMatrix<T>>(A: &M) -> T {
+    no_overflow(checked_bareiss_determinant(A))
+}
+
+// As bareiss_determinant, or None on overflow.
+pub fn checked_bareiss_determinant<T: EuclideanRing, M: BasicMatrix<T>>(A: &M) -> Option<T> {
+    assert!(A.num_rows() == A.num_cols());
+
+    let n = A.num_rows();
+    let mut B: Matrix<T> = convert(A);
+    let mut prev: T = Semiring::one();
+    let mut negate = false;
+
+    if n == 0 {
+        return Some(prev)
+    }
+
+    for k in range(0, n - 1) {
+        if B.get(k, k).is_zero() {
+            match range(k + 1, n).find(|&i| !B.get(i, k).is_zero()) {
+                Some(p) => {
+                    swap_rows(&mut B, k, p, 0);
+                    negate = !negate;
+                }
+                None => return Some(Semiring::zero())
+            }
+        }
+
+        let pivot = B.get(k, k);
+        for i in range(k + 1, n) {
+            let Bik = B.get(i, k);
+            for j in range(k + 1, n) {
+                let x = exact!(exact!(B.get(i, j).mul_exact(&pivot))
+                               .sub_exact(&exact!(Bik.mul_exact(&B.get(k, j)))));
+                let (q, _) = exact!(x.div_rem_exact(&prev));
+                B.set(i, j, q);
+            }
+        }
+        prev = pivot;
+    }
+
+    let d = B.get(n - 1, n - 1);
+    if negate { d.neg_exact() } else { Some(d) }
+}
+
+// The row style Hermite normal form. Returns (H, U) with U A = H and U
+// unimodular. H is in row echelon form, each pivot is positive, and the
+// entries above a pivot lie in [0, pivot). Fails on overflow.
+pub fn hermite_normal_form<T: EuclideanRing,
+                           M: BasicMatrix<T>,
+                           R: BasicMatrix<T> + Create<T>>(A: &M) -> (R, R) {
+    no_overflow(checked_hermite_normal_form(A))
+}
+
+// As hermite_normal_form, or None on overflow.
+pub fn checked_hermite_normal_form<T: EuclideanRing,
+                                   M: BasicMatrix<T>,
+                                   R: BasicMatrix<T> + Create<T>>(A: &M) -> Option<(R, R)> {
+    let m = A.num_rows();
+    let n = A.num_cols();
+    let mut H: R = convert(A);
+    let mut U: R = identity(m);
+
+    let mut r = 0;
+    for c in range(0, n) {
+        if r == m {
+            break
+        }
+
+        // Euclid's algorithm on column c, run on whole rows, leaves the
+        // gcd of the column below r in row r.
+        for i in range(r + 1, m) {
+            while !H.get(i, c).is_zero() {
+                let (q, _) = exact!(H.get(r, c).div_rem_exact(&H.get(i, c)));
+                exact!(sub_row(&mut H, r, i, &q));
+                exact!(sub_row(&mut U, r, i, &q));
+                swap_rows(&mut H, r, i, 0);
+                swap_rows(&mut U, r, i, 0);
+            }
+        }
+
+        let pivot = H.get(r, c);
+        if pivot.is_zero() {
+            continue
+        }
+        let zero: T = Semiring::zero();
+        if pivot < zero {
+            exact!(negate_row(&mut H, r));
+            exact!(negate_row(&mut U, r));
+        }
+
+        let pivot = H.get(r, c);
+        for i in range(0, r) {
+            let q = exact!(div_floor(&H.get(i, c), &pivot));
+            if !q.is_zero() {
+                exact!(sub_row(&mut H, i, r, &q));
+                exact!(sub_row(&mut U, i, r, &q));
+            }
+        }
+        r += 1;
+    }
+    Some((H, U))
+}
+
+// The Smith normal form. Returns (S, U, V) with U A V = S and U, V
+// unimodular. S is diagonal with nonnegative entries, each dividing the
+// next, so they are the invariant factors of A. Fails on overflow.
+pub fn smith_normal_form<T: EuclideanRing,
+                         M: BasicMatrix<T>,
+                         R: BasicMatrix<T> + Create<T>>(A: &M) -> (R, R, R) {
+    no_overflow(checked_smith_normal_form(A))
+}
+
+// As smith_normal_form, or None on overflow.
+pub fn checked_smith_normal_form<T: EuclideanRing,
+                                 M: BasicMatrix<T>,
+                                 R: BasicMatrix<T> + Create<T>>(A: &M) -> Option<(R, R, R)> {
+    let m = A.num_rows();
+    let n = A.num_cols();
+    let mut S: R = convert(A);
+    let mut U: R = identity(m);
+    let mut V: R = identity(n);
+
+    for t in range(0, if m < n { m } else { n }) {
+        // Each pass either finishes position t or finds a nonzero
+        // remainder smaller than the pivot, so this terminates.
+        loop {
+            // Move the smallest nonzero entry left to (t, t).
+            let mut best: Option<(uint, uint, T)> = None;
+            for i in range(t, m) {
+                for j in range(t, n) {
+                    let x = S.get(i, j);
+                    if x.is_zero() {
+                        continue
+                    }
+                    let a = exact!(abs(&x));
+                    let smaller = match best {
+                        Some((_, _, ref b)) => a < *b,
+                        None => true
+                    };
+                    if smaller {
+                        best = Some((i, j, a));
+                    }
+                }
+            }
+            let (p, q) = match best {
+                Some((p, q, _)) => (p, q),
+                None => return Some((S, U, V))
+            };
+            if p != t {
+                swap_rows(&mut S, t, p, 0);
+                swap_rows(&mut U, t, p, 0);
+            }
+            if q != t {
+                swap_cols(&mut S, t, q);
+                swap_cols(&mut V, t, q);
+            }
+
+            let pivot = S.get(t, t);
+            let mut done = true;
+            for i in range(t + 1, m) {
+                let (k, r) = exact!(S.get(i, t).div_rem_exact(&pivot));
+                exact!(sub_row(&mut S, i, t, &k));
+                exact!(sub_row(&mut U, i, t, &k));
+                done = done && r.is_zero();
+            }
+            for j in range(t + 1, n) {
+                let (k, r) = exact!(S.get(t, j).div_rem_exact(&pivot));
+                exact!(sub_col(&mut S, j, t, &k));
+                exact!(sub_col(&mut V, j, t, &k));
+                done = done && r.is_zero();
+            }
+            if !done {
+                continue
+            }
+
+            // Row and column t are clear. If the pivot fails to divide
+            // some remaining entry, adding that row to row t brings it
+            // in, and the next pass finds a smaller pivot.
+            let mut bad = None;
+            for i in range(t + 1, m) {
+                for j in range(t + 1, n) {
+                    let (_, r) = exact!(S.get(i, j).div_rem_exact(&pivot));
+                    if !r.is_zero() {
+                        bad = Some(i);
+                        break
+                    }
+                }
+                if bad.is_some() {
+                    break
+                }
+            }
+            match bad {
+                Some(i) => {
+                    let minus_one: T = Semiring::one();
+                    let minus_one = exact!(minus_one.neg_exact());
+                    exact!(sub_row(&mut S, t, i, &minus_one));
+                    exact!(sub_row(&mut U, t, i, &minus_one));
+                }
+                None => break
+            }
+        }
+
+        let zero: T = Semiring::zero();
+        if S.get(t, t) < zero {
+            exact!(negate_row(&mut S, t));
+            exact!(negate_row(&mut U, t));
+        }
+    }
+    Some((S, U, V))
+}
@@ -2,23 +2,45 @@
 #![allow(non_snake_case_functions)]
 
 use std::mem;
-use std::num;
 use std::ops;
 
+use scalar::Conjugate;
+
+// The element structures live with the scalar types; they are
+// re-exported here since every algorithm is bounded by one of them.
+pub use scalar::{Semiring, Ring, Field, RealField};
+
 pub mod algorithms;
+pub mod banded;
+pub mod bit;
+pub mod diagonal;
+pub mod dim;
+pub mod fixed;
 pub mod generate;
+pub mod integer;
 pub mod util;
+pub mod vector;
 pub mod par;
-
-// Sort of corresponds to the abstract algebra notion of a ring.
-pub trait Ring : ops::Add<Self, Self> + ops::Mul<Self, Self> + FromPrimitive{
-    fn one() -> Self;
-    fn zero() -> Self;
+pub mod sparse;
+pub mod symmetric;
+pub mod tape;
+pub mod triangular;
+
+// Which triangle of a matrix may hold nonzeros.
+#[deriving(Clone, PartialEq, Show)]
+pub enum Triangle {
+    Lower,
+    Upper
 }
 
-impl<T: Num + FromPrimitive> Ring for T {
-    fn one() -> T { num::from_int(1).unwrap() }
-    fn zero() -> T { num::from_int(0).unwrap() }
+impl Triangle {
+    /// The triangle seen through a transpose.
+    pub fn flip(&self) -> Triangle {
+        match *self {
+            Lower => Upper,
+            Upper => Lower
+        }
+    }
 }
 
 pub trait BasicMatrix<T> {
@@ -32,6 +54,24 @@ pub trait BasicMatrix<T> {
     /// Returns the number of columns in the matrix.
     fn num_cols(&self) -> uint;
 
+    /// Returns the triangle that all nonzeros are confined to, if the
+    /// matrix type guarantees one. Algorithms use this to pick
+    /// triangular code paths.
+    fn triangle(&self) -> Option<Triangle> { None }
+
+    /// Returns the boundaries of the square diagonal blocks that all
+    /// nonzeros are confined to, if the matrix type guarantees them.
+    /// Block k covers rows and columns b[k] to b[k + 1]. Algorithms
+    /// use this to work block by block.
+    fn diagonal_blocks(&self) -> Option<Vec<uint>> { None }
+
+    /// Returns the entries in row-major order, if the matrix stores
+    /// them contiguously that way.
+    fn row_major_data<'a>(&'a self) -> Option<&'a [T]> { None }
+    /// Returns the entries in column-major order, if the matrix
+    /// stores them contiguously that way.
+    fn col_major_data<'a>(&'a self) -> Option<&'a [T]> { None }
+
     #[lang="index"]
     fn index(&self, ix: &(uint, uint)) -> T {
         let &(i, j) = ix;
@@ -100,6 +140,21 @@ impl<'r, T, M: BasicMatrix<T>> BasicMatrix<T> for &'r M {
     fn num_cols(&self) -> uint {
         self.num_rows()
     }
+
+    fn triangle(&self) -> Option<Triangle> {
+        self.triangle()
+    }
+
+    fn diagonal_blocks(&self) -> Option<Vec<uint>> {
+        self.diagonal_blocks()
+    }
+
+    fn row_major_data<'a>(&'a self) -> Option<&'a [T]> {
+        self.row_major_data()
+    }
+    fn col_major_data<'a>(&'a self) -> Option<&'a [T]> {
+        self.col_major_data()
+    }
 }
 
 pub fn row<'a, T, M: BasicMatrix<T>>(m: &'a M, i: uint)
@@ -153,6 +208,10 @@ impl<T: Clone> BasicMatrix<T> for Matrix<T> {
 
     fn num_rows(&self) -> uint { self.rows }
     fn num_cols(&self) -> uint { self.cols }
+
+    fn row_major_data<'a>(&'a self) -> Option<&'a [T]> {
+        Some(self.data.as_slice())
+    }
 }
 
 impl<T: Clone> Create<T> for Matrix<T> {
@@ -199,6 +258,38 @@ impl<'r, T, M: BasicMatrix<T>> BasicMatrix<T> for SubMatrix<'r, T, M> {
     fn num_rows(&self) -> uint { self.rows }
     fn num_cols(&self) -> uint { self.cols }
 
+    // A square block on the diagonal keeps the structure of its base.
+    fn triangle(&self) -> Option<Triangle> {
+        if self.i == self.j && self.rows == self.cols {
+            self.base.triangle()
+        }
+        else {
+            None
+        }
+    }
+
+    // A band of whole rows (or whole columns) is still contiguous.
+    fn row_major_data<'a>(&'a self) -> Option<&'a [T]> {
+        if self.j == 0 && self.cols == self.base.num_cols() {
+            self.base.row_major_data().map(|d| {
+                d.slice(self.i * self.cols, (self.i + self.rows) * self.cols)
+            })
+        }
+        else {
+            None
+        }
+    }
+    fn col_major_data<'a>(&'a self) -> Option<&'a [T]> {
+        if self.i == 0 && self.rows == self.base.num_rows() {
+            self.base.col_major_data().map(|d| {
+                d.slice(self.j * self.rows, (self.j + self.cols) * self.rows)
+            })
+        }
+        else {
+            None
+        }
+    }
+
     #[inline(always)]
     fn get(&self, i: uint, j: uint) -> T {
         if i < self.rows && j < self.cols {
@@ -256,6 +347,22 @@ impl<'r, T, M: BasicMatrix<T>> BasicMatrix<T> for TransposeMatrix<'r, T, M> {
         self.get_ref().num_rows()
     }
 
+    fn triangle(&self) -> Option<Triangle> {
+        self.get_ref().triangle().map(|t| t.flip())
+    }
+
+    fn diagonal_blocks(&self) -> Option<Vec<uint>> {
+        self.get_ref().diagonal_blocks()
+    }
+
+    // Transposing swaps the storage order.
+    fn row_major_data<'a>(&'a self) -> Option<&'a [T]> {
+        self.get_ref().col_major_data()
+    }
+    fn col_major_data<'a>(&'a self) -> Option<&'a [T]> {
+        self.get_ref().row_major_data()
+    }
+
     #[inline(always)]
     fn get(&self, i: uint, j: uint) -> T {
         self.get_ref().get(j, i)
@@ -266,3 +373,60 @@ impl<'r, T, M: BasicMatrix<T>> BasicMatrix<T> for TransposeMatrix<'r, T, M> {
         self.get_mut().set(j, i, x)
     }
 }
+
+// The conjugate (Hermitian) transpose. For real element types this is
+// the same as TransposeMatrix.
+pub struct ConjugateTransposeMatrix<'r, T, M>(&'r M);
+
+impl<'r, T, M> Clone for ConjugateTransposeMatrix<'r, T, M> {
+    fn clone(&self) -> ConjugateTransposeMatrix<'r, T, M> {
+        match self {
+            &ConjugateTransposeMatrix(m) => ConjugateTransposeMatrix(m)
+        }
+    }
+}
+
+pub fn conj_transpose<'r, T, M>(m: &'r M) -> ConjugateTransposeMatrix<'r, T, M> {
+    ConjugateTransposeMatrix(m)
+}
+
+impl<'r, T, M> ConjugateTransposeMatrix<'r, T, M> {
+    fn get_ref(&self) -> &'r M {
+        match *self {
+            ConjugateTransposeMatrix(m) => m
+        }
+    }
+
+    fn get_mut(&mut self) -> &'r mut M {
+        match self {
+            &ConjugateTransposeMatrix(m) => unsafe { mem::transmute(m) }
+        }
+    }
+}
+
+impl<'r, T: Conjugate, M: BasicMatrix<T>> BasicMatrix<T> for ConjugateTransposeMatrix<'r, T, M> {
+    fn num_rows(&self) -> uint {
+        self.get_ref().num_cols()
+    }
+    fn num_cols(&self) -> uint {
+        self.get_ref().num_rows()
+    }
+
+    fn triangle(&self) -> Option<Triangle> {
+        self.get_ref().triangle().map(|t| t.flip())
+    }
+
+    fn diagonal_blocks(&self) -> Option<Vec<uint>> {
+        self.get_ref().diagonal_blocks()
+    }
+
+    #[inline(always)]
+    fn get(&self, i: uint, j: uint) -> T {
+        self.get_ref().get(j, i).conj()
+    }
+
+    #[inline(always)]
+    fn set(&mut self, i: uint, j: uint, x: T) {
+        self.get_mut().set(j, i, x.conj())
+    }
+}
@@ -1,11 +1,15 @@
 #![allow(uppercase_variables)]
 
+use std::cmp;
+use std::os;
 use std::sync::Arc;
 use std::sync::Future;
 
-use matrix::{BasicMatrix, Create, SubMatrix};
+use matrix::{BasicMatrix, Create, Matrix, SubMatrix, Triangle, Vector, Semiring};
 use matrix::algorithms;
-use matrix::algorithms::{concat_cols, concat_rows, mat_add_inplace};
+use matrix::algorithms::{concat_cols, concat_rows, mat_add_inplace,
+                         TransposeFlag, NoTrans, Trans};
+use matrix::vector::DenseVector;
 
 type SubCoords = ((uint, uint), (uint, uint));
 
@@ -22,7 +26,7 @@ fn subdivide_coords(x: SubCoords) -> (SubCoords, SubCoords, SubCoords, SubCoords
      ((i + n2, j + m2), (n2a, m2a)))
 }
 
-pub fn mat_mul<T: Num + FromPrimitive, LHS: BasicMatrix<T> + Send + Clone, RHS: BasicMatrix<T> + Send + Clone, Res: BasicMatrix<T> + Create<T> + Send>
+pub fn mat_mul<T: Semiring, LHS: BasicMatrix<T> + Send + Clone, RHS: BasicMatrix<T> + Send + Clone, Res: BasicMatrix<T> + Create<T> + Send>
 (lhs: &LHS, rhs: &RHS) -> Res {
     assert!(lhs.num_cols() == rhs.num_rows());
 
@@ -30,7 +34,7 @@ pub fn mat_mul<T: Num + FromPrimitive, LHS: BasicMatrix<T> + Send + Clone, RHS:
             rhs.clone(), ((0, 0), (rhs.num_rows(), rhs.num_cols())))
 }
 
-fn sub_mul<T: Num + FromPrimitive, LHS: BasicMatrix<T> + Send + Clone, RHS: BasicMatrix<T> + Send + Clone, Res: BasicMatrix<T> + Create<T> + Send>
+fn sub_mul<T: Semiring, LHS: BasicMatrix<T> + Send + Clone, RHS: BasicMatrix<T> + Send + Clone, Res: BasicMatrix<T> + Create<T> + Send>
 (lhs: LHS, lc: SubCoords, rhs: RHS, rc: SubCoords) -> Res
 {
     static BLOCK_SIZE: uint = 1 << 13;
@@ -109,6 +113,133 @@ fn sub_mul<T: Num + FromPrimitive, LHS: BasicMatrix<T> + Send + Clone, RHS: Basi
     }
 }
 
+// Parallel gemv, y = alpha A x + beta y. Blocks of rows are handed to
+// separate tasks, which each run algorithms::gemv on their block.
+pub fn gemv<T: Semiring + Send + Share, M: BasicMatrix<T> + Send + Clone, V: Vector<T>>
+(alpha: T, A: &M, x: &V, beta: T, y: &mut DenseVector<T>)
+{
+    static BLOCK_SIZE: uint = 1 << 13;
+
+    let N = A.num_rows();
+    let K = A.num_cols();
+    assert!(K == x.len() && N == y.len());
+    if N == 0 || K == 0 {
+        return algorithms::gemv(alpha, A, x, beta, y)
+    }
+
+    // Rows per task, so that each task covers about BLOCK_SIZE entries.
+    let rows = cmp::max(1, BLOCK_SIZE / cmp::max(K, 1));
+
+    let x = Arc::new(DenseVector::from_vector(x));
+    let mut futures = Vec::new();
+    let mut lo = 0;
+    while lo < N {
+        let n = if lo + rows > N { N - lo } else { rows };
+        let Ac = A.clone();
+        let xc = x.clone();
+        let alpha = alpha.clone();
+        futures.push(Future::spawn(proc() {
+            let mut yb = DenseVector::zeros(n);
+            algorithms::gemv(alpha, &SubMatrix(&Ac, lo, 0, n, K), &*xc,
+                             Semiring::zero(), &mut yb);
+            yb
+        }));
+        lo += n;
+    }
+
+    let mut i = 0;
+    for f in futures.move_iter() {
+        let yb = f.unwrap();
+        for k in range(0, yb.len()) {
+            let yi = y.get(i);
+            let ax = yb.get(k);
+            y.set(i, if beta.is_zero() { ax } else { ax + beta * yi });
+            i += 1;
+        }
+    }
+}
+
+// Parallel gemm, C = alpha op(A) op(B) + beta C. The operands are
+// shared between the tasks through their Arcs, not copied. C is split
+// into blocks of rows, and each task runs algorithms::gemm_blocked on
+// one block, including the beta C term.
+//
+// A spawned task cannot borrow the caller's C, so each block is copied
+// into a scratch matrix that the task owns and hands back when it is
+// done, and then copied out into C. That is one read and one write of
+// each entry of C, against k multiply-adds per entry. Tasks run one
+// wave per core at a time, and each wave reuses the scratch matrices
+// the previous wave returned, so there is about one allocation per
+// worker rather than one per block.
+pub fn gemm<T: Semiring + Send + Share,
+            LHS: BasicMatrix<T> + Send + Share,
+            RHS: BasicMatrix<T> + Send + Share,
+            Dest: BasicMatrix<T>>
+(ta: TransposeFlag, tb: TransposeFlag,
+ alpha: T, A: &Arc<LHS>, B: &Arc<RHS>, beta: T, C: &mut Dest)
+{
+    static BLOCK_SIZE: uint = 1 << 13;
+
+    let (m, n, k) = algorithms::gemm_dims(ta, A, tb, B, C);
+    if m == 0 || n == 0 || k == 0 {
+        return algorithms::gemm(ta, tb, alpha, A, B, beta, C)
+    }
+
+    let rows = cmp::max(1, BLOCK_SIZE / n);
+    let workers = cmp::max(1, os::num_cpus());
+
+    let mut scratch: Vec<Matrix<T>> = Vec::new();
+    let mut lo = 0;
+    while lo < m {
+        let mut futures = Vec::new();
+        while lo < m && futures.len() < workers {
+            let h = if lo + rows > m { m - lo } else { rows };
+            let r0 = lo;
+
+            // Only the last block can be shorter, and it gets its own.
+            let reuse = scratch.last().map_or(false, |Cb| Cb.num_rows() == h);
+            let Cb: Matrix<T> = if reuse {
+                let mut Cb = scratch.pop().unwrap();
+                for i in range(0, h) {
+                    for j in range(0, n) {
+                        Cb.set(i, j, C.get(r0 + i, j));
+                    }
+                }
+                Cb
+            }
+            else {
+                Create::create(h, n, |i, j| C.get(r0 + i, j))
+            };
+
+            let Ac = A.clone();
+            let Bc = B.clone();
+            let alpha = alpha.clone();
+            let beta = beta.clone();
+            futures.push(Future::spawn(proc() {
+                let mut Cb = Cb;
+                match ta {
+                    NoTrans => algorithms::gemm_blocked(
+                        ta, tb, alpha, &SubMatrix(&Ac, r0, 0, h, k), &Bc, beta, &mut Cb),
+                    Trans => algorithms::gemm_blocked(
+                        ta, tb, alpha, &SubMatrix(&Ac, 0, r0, k, h), &Bc, beta, &mut Cb)
+                }
+                (r0, Cb)
+            }));
+            lo += h;
+        }
+
+        for f in futures.move_iter() {
+            let (r0, Cb) = f.unwrap();
+            for i in range(0, Cb.num_rows()) {
+                for j in range(0, n) {
+                    C.set(r0 + i, j, Cb.get(i, j));
+                }
+            }
+            scratch.push(Cb);
+        }
+    }
+}
+
 impl<T, M: BasicMatrix<T> + Send> BasicMatrix<T> for Arc<M> {
     fn get(&self, i: uint, j: uint) -> T {
         (*self).get(i, j)
@@ -120,4 +251,14 @@ impl<T, M: BasicMatrix<T> + Send> BasicMatrix<T> for Arc<M> {
 
     fn num_rows(&self) -> uint { (*self).num_rows() }
     fn num_cols(&self) -> uint { (*self).num_cols() }
+
+    fn triangle(&self) -> Option<Triangle> { (*self).triangle() }
+    fn diagonal_blocks(&self) -> Option<Vec<uint>> { (*self).diagonal_blocks() }
+
+    fn row_major_data<'a>(&'a self) -> Option<&'a [T]> {
+        (**self).row_major_data()
+    }
+    fn col_major_data<'a>(&'a self) -> Option<&'a [T]> {
+        (**self).col_major_data()
+    }
 }
@@ -0,0 +1,210 @@
+// Sparse counterparts of the routines in matrix::algorithms. Both
+// operands and the result share one storage format.
+
+#![allow(uppercase_variables)]
+
+use std::uint;
+
+use matrix::{Semiring, Ring};
+use matrix::sparse::CompressedMatrix;
+use matrix::sparse::ordering::invert_perm;
+
+// Gustavson's algorithm for the compressed lines `lo` to `hi` of `x`.
+// Each inner index of `x` selects a line of `y`, and the lines are
+// scattered into a dense accumulator. When `swap` is set the scalar
+// products are formed as y * x, which keeps the operand order right
+// for column compressed storage.
+pub fn gustavson_lines<T: Semiring, X: CompressedMatrix<T>, Y: CompressedMatrix<T>>
+(x: &X, y: &Y, lo: uint, hi: uint, swap: bool)
+    -> (Vec<uint>, Vec<uint>, Vec<T>)
+{
+    let xp = x.outer_ptr();
+    let xi = x.inner_indices();
+    let xv = x.values();
+    let yp = y.outer_ptr();
+    let yi = y.inner_indices();
+    let yv = y.values();
+
+    let n = y.inner_dim();
+    let mut acc: Vec<T> = Vec::from_elem(n, Semiring::zero());
+    let mut mark = Vec::from_elem(n, uint::MAX);
+
+    let mut ptr = Vec::with_capacity(hi - lo + 1);
+    let mut idx = Vec::new();
+    let mut vals = Vec::new();
+    ptr.push(0);
+
+    for i in range(lo, hi) {
+        let start = idx.len();
+        for p in range(xp[i], xp[i + 1]) {
+            let k = xi[p];
+            for q in range(yp[k], yp[k + 1]) {
+                let j = yi[q];
+                let prod = if swap { yv[q] * xv[p] } else { xv[p] * yv[q] };
+                if *mark.get(j) != i {
+                    *mark.get_mut(j) = i;
+                    *acc.get_mut(j) = prod;
+                    idx.push(j);
+                }
+                else {
+                    let s = *acc.get(j) + prod;
+                    *acc.get_mut(j) = s;
+                }
+            }
+        }
+
+        idx.mut_slice_from(start).sort();
+        for p in range(start, idx.len()) {
+            vals.push(acc.get(*idx.get(p)).clone());
+        }
+        ptr.push(idx.len());
+    }
+
+    (ptr, idx, vals)
+}
+
+pub fn mat_mul<T: Semiring, M: CompressedMatrix<T>>(lhs: &M, rhs: &M) -> M
+{
+    if lhs.num_cols() != rhs.num_rows() {
+        fail!(format!("Incompatible matrix sizes. LHS: {:?}, RHS: {:?}",
+                   (lhs.num_rows(), lhs.num_cols()),
+                   (rhs.num_rows(), rhs.num_cols())))
+    }
+
+    // For CSR, row i of the product combines rows of rhs. For CSC,
+    // column j of the product combines columns of lhs.
+    if lhs.is_row_major() {
+        let (ptr, idx, vals) =
+            gustavson_lines(lhs, rhs, 0, lhs.outer_dim(), false);
+        CompressedMatrix::from_parts(lhs.num_rows(), rhs.num_cols(),
+                                     ptr, idx, vals)
+    }
+    else {
+        let (ptr, idx, vals) =
+            gustavson_lines(rhs, lhs, 0, rhs.outer_dim(), true);
+        CompressedMatrix::from_parts(rhs.num_cols(), lhs.num_rows(),
+                                     ptr, idx, vals)
+    }
+}
+
+// Merges the lines of two matrices with identical shape and format.
+// `f` receives None for an entry missing from that operand, and
+// entries for which it returns None are not stored.
+fn merge<T: Clone, M: CompressedMatrix<T>>
+(lhs: &M, rhs: &M, f: |Option<&T>, Option<&T>| -> Option<T>) -> M
+{
+    if lhs.num_cols() != rhs.num_cols() || lhs.num_rows() != rhs.num_rows() {
+        fail!(format!("Incompatible matrix sizes. LHS: {:?}, RHS: {:?}",
+                   (lhs.num_rows(), lhs.num_cols()),
+                   (rhs.num_rows(), rhs.num_cols())))
+    }
+    assert!(lhs.is_row_major() == rhs.is_row_major());
+
+    let ap = lhs.outer_ptr();
+    let ai = lhs.inner_indices();
+    let av = lhs.values();
+    let bp = rhs.outer_ptr();
+    let bi = rhs.inner_indices();
+    let bv = rhs.values();
+
+    let mut ptr = Vec::with_capacity(lhs.outer_dim() + 1);
+    let mut idx = Vec::new();
+    let mut vals = Vec::new();
+    ptr.push(0);
+
+    for i in range(0, lhs.outer_dim()) {
+        let mut p = ap[i];
+        let mut q = bp[i];
+        while p < ap[i + 1] || q < bp[i + 1] {
+            let (j, x) = if q >= bp[i + 1]
+                || (p < ap[i + 1] && ai[p] < bi[q])
+            {
+                p += 1;
+                (ai[p - 1], f(Some(&av[p - 1]), None))
+            }
+            else if p >= ap[i + 1] || bi[q] < ai[p] {
+                q += 1;
+                (bi[q - 1], f(None, Some(&bv[q - 1])))
+            }
+            else {
+                p += 1;
+                q += 1;
+                (ai[p - 1], f(Some(&av[p - 1]), Some(&bv[q - 1])))
+            };
+
+            match x {
+                Some(x) => {
+                    idx.push(j);
+                    vals.push(x);
+                }
+                None => ()
+            }
+        }
+        ptr.push(idx.len());
+    }
+
+    CompressedMatrix::from_parts(lhs.outer_dim(), lhs.inner_dim(),
+                                 ptr, idx, vals)
+}
+
+pub fn mat_add<T: Semiring, M: CompressedMatrix<T>>(lhs: &M, rhs: &M) -> M
+{
+    merge(lhs, rhs, |a, b| match (a, b) {
+        (Some(a), Some(b)) => Some(*a + *b),
+        (Some(a), None) => Some(a.clone()),
+        (None, Some(b)) => Some(b.clone()),
+        (None, None) => None
+    })
+}
+
+pub fn mat_sub<T: Ring, M: CompressedMatrix<T>>(lhs: &M, rhs: &M) -> M
+{
+    merge(lhs, rhs, |a, b| match (a, b) {
+        (Some(a), Some(b)) => Some(*a - *b),
+        (Some(a), None) => Some(a.clone()),
+        (None, Some(b)) => Some(-*b),
+        (None, None) => None
+    })
+}
+
+// Elementwise product. Only entries stored in both operands survive.
+pub fn hadamard<T: Semiring, M: CompressedMatrix<T>>(lhs: &M, rhs: &M) -> M
+{
+    merge(lhs, rhs, |a, b| match (a, b) {
+        (Some(a), Some(b)) => Some(*a * *b),
+        _ => None
+    })
+}
+
+// Returns P A Pt, where row and column k of the result are row and
+// column perm[k] of A. This touches only the stored entries.
+pub fn permute_symmetric<T: Clone, M: CompressedMatrix<T>>(A: &M, perm: &[uint]) -> M
+{
+    assert!(A.num_rows() == A.num_cols());
+    assert!(perm.len() == A.num_rows());
+
+    let n = A.outer_dim();
+    let pinv = invert_perm(perm);
+    let ptr = A.outer_ptr();
+    let idx = A.inner_indices();
+    let vals = A.values();
+
+    let mut out_ptr = Vec::with_capacity(n + 1);
+    let mut out_idx = Vec::with_capacity(A.nnz());
+    let mut out_vals = Vec::with_capacity(A.nnz());
+    out_ptr.push(0);
+    for k in range(0, n) {
+        let i = perm[k];
+        let mut line: Vec<(uint, T)> = range(ptr[i], ptr[i + 1])
+            .map(|p| (*pinv.get(idx[p]), vals[p].clone()))
+            .collect();
+        line.sort_by(|&(a, _), &(b, _)| a.cmp(&b));
+        for (j, x) in line.move_iter() {
+            out_idx.push(j);
+            out_vals.push(x);
+        }
+        out_ptr.push(out_idx.len());
+    }
+
+    CompressedMatrix::from_parts(n, n, out_ptr, out_idx, out_vals)
+}
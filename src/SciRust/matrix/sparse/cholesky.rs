@@ -0,0 +1,289 @@
+// Sparse Cholesky factorization, A = P' L Lt P.
+//
+// The factorization is split into a symbolic phase, which depends only
+// on the sparsity pattern of A, and a numeric phase. A SymbolicCholesky
+// can be reused to refactor any matrix with the same pattern.
+//
+// This follows the up-looking algorithm from Tim Davis's "Direct
+// Methods for Sparse Linear Systems".
+
+#![allow(uppercase_variables)]
+
+use matrix::{BasicMatrix, Semiring, Field, RealField};
+use matrix::sparse::{CompressedMatrix, CscMatrix};
+use matrix::sparse::ordering::{amd, invert_perm};
+
+pub struct SymbolicCholesky {
+    n: uint,
+    // perm[k] is the row and column of A that becomes row and column k
+    // of the factor.
+    perm: Vec<uint>,
+    pinv: Vec<uint>,
+    // Elimination tree of P A Pt.
+    parent: Vec<Option<uint>>,
+    // Column pointers of L.
+    col_ptr: Vec<uint>
+}
+
+impl SymbolicCholesky {
+    pub fn perm<'a>(&'a self) -> &'a [uint] { self.perm.as_slice() }
+
+    /// The elimination tree. The root of each tree has no parent.
+    pub fn etree<'a>(&'a self) -> &'a [Option<uint>] { self.parent.as_slice() }
+
+    /// Returns the number of nonzeros in L.
+    pub fn nnz(&self) -> uint { *self.col_ptr.get(self.n) }
+}
+
+// The upper triangle of P A Pt. Only the upper triangle of A is read.
+fn symperm_upper<T: Semiring>(A: &CscMatrix<T>, pinv: &[uint]) -> CscMatrix<T> {
+    let n = A.num_cols();
+    let ptr = A.outer_ptr();
+    let idx = A.inner_indices();
+    let vals = A.values();
+
+    let mut entries = Vec::with_capacity(A.nnz());
+    for j in range(0, n) {
+        for p in range(ptr[j], ptr[j + 1]) {
+            let i = idx[p];
+            if i <= j {
+                let (i2, j2) = (pinv[i], pinv[j]);
+                if i2 <= j2 {
+                    entries.push((i2, j2, vals[p].clone()))
+                }
+                else {
+                    entries.push((j2, i2, vals[p].clone()))
+                }
+            }
+        }
+    }
+    CscMatrix::from_triplets(n, n, entries.as_slice())
+}
+
+// The elimination tree of a matrix given by its upper triangle.
+fn etree<T: Semiring>(C: &CscMatrix<T>) -> Vec<Option<uint>> {
+    let n = C.num_cols();
+    let ptr = C.outer_ptr();
+    let idx = C.inner_indices();
+
+    let mut parent = Vec::from_elem(n, None);
+    // Path compressed ancestors, so finding the root is cheap.
+    let mut ancestor: Vec<Option<uint>> = Vec::from_elem(n, None);
+    for k in range(0, n) {
+        for p in range(ptr[k], ptr[k + 1]) {
+            let mut i = Some(idx[p]);
+            loop {
+                match i {
+                    Some(r) if r < k => {
+                        let next = *ancestor.get(r);
+                        *ancestor.get_mut(r) = Some(k);
+                        if next.is_none() {
+                            *parent.get_mut(r) = Some(k);
+                        }
+                        i = next;
+                    }
+                    _ => break
+                }
+            }
+        }
+    }
+    parent
+}
+
+// Finds the pattern of row k of L by walking up the elimination tree
+// from each nonzero in column k of C. The pattern is left in
+// stack[top..n] in topological order, and top is returned. `mark` must
+// not contain k on entry.
+fn ereach<T: Semiring>(C: &CscMatrix<T>, k: uint, parent: &[Option<uint>],
+                       stack: &mut [uint], mark: &mut [uint]) -> uint {
+    let n = C.num_cols();
+    let ptr = C.outer_ptr();
+    let idx = C.inner_indices();
+
+    let mut top = n;
+    mark[k] = k;
+    for p in range(ptr[k], ptr[k + 1]) {
+        let mut i = idx[p];
+        if i > k {
+            continue
+        }
+
+        let mut len = 0;
+        while mark[i] != k {
+            stack[len] = i;
+            len += 1;
+            mark[i] = k;
+            i = match parent[i] {
+                Some(i) => i,
+                None => break
+            };
+        }
+        while len > 0 {
+            top -= 1;
+            len -= 1;
+            stack[top] = stack[len];
+        }
+    }
+    top
+}
+
+pub fn symbolic_cholesky<T: Semiring>(A: &CscMatrix<T>) -> SymbolicCholesky {
+    symbolic_cholesky_with_order(A, amd(A))
+}
+
+// Symbolic analysis with a caller supplied ordering. Use the identity
+// permutation to factor A as given.
+pub fn symbolic_cholesky_with_order<T: Semiring>(A: &CscMatrix<T>, perm: Vec<uint>)
+    -> SymbolicCholesky
+{
+    assert!(A.num_rows() == A.num_cols());
+    let n = A.num_rows();
+    assert!(perm.len() == n);
+
+    let pinv = invert_perm(perm.as_slice());
+    let C = symperm_upper(A, pinv.as_slice());
+    let parent = etree(&C);
+
+    // Column counts of L, counted one row pattern at a time.
+    let mut count = Vec::from_elem(n, 1u);
+    let mut stack = Vec::from_elem(n, 0u);
+    let mut mark = Vec::from_elem(n, n);
+    for k in range(0, n) {
+        let top = ereach(&C, k, parent.as_slice(),
+                         stack.as_mut_slice(), mark.as_mut_slice());
+        for &i in stack.slice_from(top).iter() {
+            *count.get_mut(i) += 1;
+        }
+    }
+
+    let mut col_ptr = Vec::with_capacity(n + 1);
+    col_ptr.push(0);
+    for j in range(0, n) {
+        let last = *col_ptr.get(j);
+        col_ptr.push(last + *count.get(j));
+    }
+
+    SymbolicCholesky {
+        n: n,
+        perm: perm,
+        pinv: pinv,
+        parent: parent,
+        col_ptr: col_ptr
+    }
+}
+
+// Computes the lower triangular factor L of P A Pt. Each column of L
+// stores its diagonal entry first. Fails if A is not positive
+// definite.
+pub fn numeric_cholesky<T: RealField>(A: &CscMatrix<T>, S: &SymbolicCholesky)
+    -> CscMatrix<T>
+{
+    let n = S.n;
+    assert!(A.num_rows() == n && A.num_cols() == n);
+
+    let C = symperm_upper(A, S.pinv.as_slice());
+    let cp = C.outer_ptr();
+    let ci = C.inner_indices();
+    let cx = C.values();
+
+    let zero: T = Semiring::zero();
+    let Lp = S.col_ptr.clone();
+    let mut Li = Vec::from_elem(S.nnz(), 0u);
+    let mut Lx = Vec::from_elem(S.nnz(), zero.clone());
+
+    // Next free slot in each column of L.
+    let mut next = Lp.clone();
+    let mut x = Vec::from_elem(n, zero.clone());
+    let mut stack = Vec::from_elem(n, 0u);
+    let mut mark = Vec::from_elem(n, n);
+
+    for k in range(0, n) {
+        // Solve L(0:k, 0:k) x = C(0:k, k) for row k of L.
+        let top = ereach(&C, k, S.parent.as_slice(),
+                         stack.as_mut_slice(), mark.as_mut_slice());
+        *x.get_mut(k) = zero.clone();
+        for p in range(cp[k], cp[k + 1]) {
+            if ci[p] <= k {
+                *x.get_mut(ci[p]) = cx[p].clone();
+            }
+        }
+        let mut d = x.get(k).clone();
+        *x.get_mut(k) = zero.clone();
+
+        for t in range(top, n) {
+            let i = *stack.get(t);
+            let lki = *x.get(i) / *Lx.get(*Lp.get(i));
+            *x.get_mut(i) = zero.clone();
+            for p in range(*Lp.get(i) + 1, *next.get(i)) {
+                let r = *Li.get(p);
+                let xr = x.get(r).clone();
+                *x.get_mut(r) = xr - *Lx.get(p) * lki;
+            }
+            d = d - lki * lki;
+
+            let p = *next.get(i);
+            *Li.get_mut(p) = k;
+            *Lx.get_mut(p) = lki;
+            *next.get_mut(i) = p + 1;
+        }
+
+        if d <= zero {
+            fail!(format!("Matrix is not positive definite. Pivot {:?}: {:?}",
+                          k, d))
+        }
+
+        let p = *next.get(k);
+        *Li.get_mut(p) = k;
+        *Lx.get_mut(p) = d.sqrt();
+        *next.get_mut(k) = p + 1;
+    }
+
+    CompressedMatrix::from_parts(n, n, Lp, Li, Lx)
+}
+
+// Solves L x = b in place, for L as returned by numeric_cholesky.
+pub fn lsolve<T: Field>(L: &CscMatrix<T>, x: &mut [T]) {
+    let ptr = L.outer_ptr();
+    let idx = L.inner_indices();
+    let vals = L.values();
+    assert!(x.len() == L.num_cols());
+
+    for j in range(0, x.len()) {
+        x[j] = x[j] / vals[ptr[j]];
+        for p in range(ptr[j] + 1, ptr[j + 1]) {
+            x[idx[p]] = x[idx[p]] - vals[p] * x[j];
+        }
+    }
+}
+
+// Solves Lt x = b in place, for L as returned by numeric_cholesky.
+pub fn ltsolve<T: Field>(L: &CscMatrix<T>, x: &mut [T]) {
+    let ptr = L.outer_ptr();
+    let idx = L.inner_indices();
+    let vals = L.values();
+    assert!(x.len() == L.num_cols());
+
+    for j in range(0, x.len()).rev() {
+        for p in range(ptr[j] + 1, ptr[j + 1]) {
+            x[j] = x[j] - vals[p] * x[idx[p]];
+        }
+        x[j] = x[j] / vals[ptr[j]];
+    }
+}
+
+// Solves A x = b given the factors of A.
+pub fn cholesky_solve<T: Field>(S: &SymbolicCholesky, L: &CscMatrix<T>, b: &[T])
+    -> Vec<T>
+{
+    assert!(b.len() == S.n);
+
+    let mut y: Vec<T> = S.perm.iter().map(|&i| b[i].clone()).collect();
+    lsolve(L, y.as_mut_slice());
+    ltsolve(L, y.as_mut_slice());
+
+    let mut x: Vec<T> = Vec::from_elem(S.n, Semiring::zero());
+    for (k, &i) in S.perm.iter().enumerate() {
+        *x.get_mut(i) = y.get(k).clone();
+    }
+    x
+}
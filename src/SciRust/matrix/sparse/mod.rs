@@ -0,0 +1,355 @@
+// Sparse matrices in compressed row and compressed column form.
+
+use matrix::{BasicMatrix, Create, Semiring};
+
+pub mod algorithms;
+pub mod cholesky;
+pub mod ordering;
+pub mod par;
+
+// Common view of CSR and CSC storage. An "outer" line is a row for
+// CSR and a column for CSC; the "inner" index runs along it.
+pub trait CompressedMatrix<T> : BasicMatrix<T> {
+    /// Returns true for row compressed storage, false for column.
+    fn is_row_major(&self) -> bool;
+
+    /// Returns the number of compressed lines.
+    fn outer_dim(&self) -> uint;
+    /// Returns the length of each compressed line.
+    fn inner_dim(&self) -> uint;
+
+    /// Offsets into `inner_indices` and `values` for each line.
+    fn outer_ptr<'a>(&'a self) -> &'a [uint];
+    /// Inner index of each stored entry, sorted within a line.
+    fn inner_indices<'a>(&'a self) -> &'a [uint];
+    /// Value of each stored entry.
+    fn values<'a>(&'a self) -> &'a [T];
+
+    /// Builds a matrix of the same format from raw compressed arrays.
+    fn from_parts(outer: uint, inner: uint,
+                  ptr: Vec<uint>, idx: Vec<uint>, vals: Vec<T>) -> Self;
+
+    /// Returns the number of stored entries.
+    fn nnz(&self) -> uint { self.values().len() }
+}
+
+// Shared storage for both compressed formats.
+#[deriving(Clone)]
+struct Compressed<T> {
+    outer: uint,
+    inner: uint,
+
+    ptr: Vec<uint>,
+    idx: Vec<uint>,
+    vals: Vec<T>
+}
+
+impl<T: Semiring> Compressed<T> {
+    fn new(outer: uint, inner: uint,
+           ptr: Vec<uint>, idx: Vec<uint>, vals: Vec<T>) -> Compressed<T> {
+        assert!(ptr.len() == outer + 1);
+        assert!(idx.len() == vals.len());
+        assert!(*ptr.get(outer) == idx.len());
+        Compressed {
+            outer: outer, inner: inner,
+            ptr: ptr, idx: idx, vals: vals
+        }
+    }
+
+    fn create(outer: uint, inner: uint, init: |uint, uint| -> T)
+        -> Compressed<T>
+    {
+        let mut ptr = Vec::with_capacity(outer + 1);
+        let mut idx = Vec::new();
+        let mut vals = Vec::new();
+        ptr.push(0);
+        for i in range(0, outer) {
+            for j in range(0, inner) {
+                let x = init(i, j);
+                if !x.is_zero() {
+                    idx.push(j);
+                    vals.push(x);
+                }
+            }
+            ptr.push(idx.len());
+        }
+        Compressed::new(outer, inner, ptr, idx, vals)
+    }
+
+    // Returns the position of (i, j) in the storage arrays, or the
+    // position where it would have to be inserted.
+    fn find(&self, i: uint, j: uint) -> Result<uint, uint> {
+        let mut lo = *self.ptr.get(i);
+        let mut hi = *self.ptr.get(i + 1);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let k = *self.idx.get(mid);
+            if k == j {
+                return Ok(mid)
+            }
+            else if k < j {
+                lo = mid + 1
+            }
+            else {
+                hi = mid
+            }
+        }
+        Err(lo)
+    }
+
+    fn get(&self, i: uint, j: uint) -> T {
+        if i < self.outer && j < self.inner {
+            match self.find(i, j) {
+                Ok(k) => self.vals.get(k).clone(),
+                Err(_) => Semiring::zero()
+            }
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (self.outer, self.inner)))
+        }
+    }
+
+    // Setting an entry that is not yet stored shifts the tail of the
+    // storage, so this is O(nnz). Build large matrices with `create`
+    // or `from_triplets` instead.
+    fn set(&mut self, i: uint, j: uint, x: T) {
+        if i < self.outer && j < self.inner {
+            match self.find(i, j) {
+                Ok(k) => *self.vals.get_mut(k) = x,
+                Err(k) => {
+                    if !x.is_zero() {
+                        self.idx.insert(k, j);
+                        self.vals.insert(k, x);
+                        for p in range(i + 1, self.outer + 1) {
+                            *self.ptr.get_mut(p) += 1;
+                        }
+                    }
+                }
+            }
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (self.outer, self.inner)))
+        }
+    }
+
+    // Sums duplicates and sorts each line. Triplets are (outer, inner,
+    // value).
+    fn from_triplets(outer: uint, inner: uint, entries: &[(uint, uint, T)])
+        -> Compressed<T>
+    {
+        let mut count = Vec::from_elem(outer + 1, 0u);
+        for &(i, j, _) in entries.iter() {
+            assert!(i < outer && j < inner);
+            *count.get_mut(i + 1) += 1;
+        }
+        for i in range(0, outer) {
+            let c = *count.get(i);
+            *count.get_mut(i + 1) += c;
+        }
+
+        let mut next = count.clone();
+        let mut idx = Vec::from_elem(entries.len(), 0u);
+        let mut vals: Vec<T> = Vec::from_elem(entries.len(), Semiring::zero());
+        for &(i, j, ref x) in entries.iter() {
+            let p = *next.get(i);
+            *idx.get_mut(p) = j;
+            *vals.get_mut(p) = x.clone();
+            *next.get_mut(i) = p + 1;
+        }
+
+        let mut ptr = Vec::with_capacity(outer + 1);
+        let mut out_idx = Vec::with_capacity(entries.len());
+        let mut out_vals = Vec::with_capacity(entries.len());
+        ptr.push(0);
+        for i in range(0, outer) {
+            let mut line: Vec<(uint, T)> =
+                range(*count.get(i), *count.get(i + 1))
+                .map(|p| (*idx.get(p), vals.get(p).clone()))
+                .collect();
+            line.sort_by(|&(a, _), &(b, _)| a.cmp(&b));
+
+            for (j, x) in line.move_iter() {
+                if out_idx.len() > *ptr.get(i)
+                    && *out_idx.get(out_idx.len() - 1) == j
+                {
+                    let last = out_vals.len() - 1;
+                    let s = *out_vals.get(last) + x;
+                    *out_vals.get_mut(last) = s;
+                }
+                else {
+                    out_idx.push(j);
+                    out_vals.push(x);
+                }
+            }
+            ptr.push(out_idx.len());
+        }
+
+        Compressed::new(outer, inner, ptr, out_idx, out_vals)
+    }
+
+    // The same matrix with the roles of outer and inner swapped.
+    fn transpose(&self) -> Compressed<T> {
+        let mut count = Vec::from_elem(self.inner + 1, 0u);
+        for &j in self.idx.iter() {
+            *count.get_mut(j + 1) += 1;
+        }
+        for j in range(0, self.inner) {
+            let c = *count.get(j);
+            *count.get_mut(j + 1) += c;
+        }
+
+        let mut next = count.clone();
+        let mut idx = Vec::from_elem(self.idx.len(), 0u);
+        let mut vals: Vec<T> = Vec::from_elem(self.vals.len(), Semiring::zero());
+        for i in range(0, self.outer) {
+            for p in range(*self.ptr.get(i), *self.ptr.get(i + 1)) {
+                let j = *self.idx.get(p);
+                let q = *next.get(j);
+                *idx.get_mut(q) = i;
+                *vals.get_mut(q) = self.vals.get(p).clone();
+                *next.get_mut(j) = q + 1;
+            }
+        }
+
+        Compressed::new(self.inner, self.outer, count, idx, vals)
+    }
+}
+
+// A matrix in Compressed Sparse Row format.
+#[deriving(Clone)]
+pub struct CsrMatrix<T> {
+    data: Compressed<T>
+}
+
+// A matrix in Compressed Sparse Column format.
+#[deriving(Clone)]
+pub struct CscMatrix<T> {
+    data: Compressed<T>
+}
+
+impl<T: Semiring> CsrMatrix<T> {
+    /// Creates an empty `rows` x `cols` matrix.
+    pub fn new(rows: uint, cols: uint) -> CsrMatrix<T> {
+        CsrMatrix {
+            data: Compressed::new(rows, cols,
+                                  Vec::from_elem(rows + 1, 0u),
+                                  Vec::new(), Vec::new())
+        }
+    }
+
+    /// Builds a matrix from (row, column, value) triplets. Duplicate
+    /// entries are summed.
+    pub fn from_triplets(rows: uint, cols: uint, entries: &[(uint, uint, T)])
+        -> CsrMatrix<T>
+    {
+        CsrMatrix { data: Compressed::from_triplets(rows, cols, entries) }
+    }
+
+    /// Converts to Compressed Sparse Column format.
+    pub fn to_csc(&self) -> CscMatrix<T> {
+        CscMatrix { data: self.data.transpose() }
+    }
+}
+
+impl<T: Semiring> CscMatrix<T> {
+    /// Creates an empty `rows` x `cols` matrix.
+    pub fn new(rows: uint, cols: uint) -> CscMatrix<T> {
+        CscMatrix {
+            data: Compressed::new(cols, rows,
+                                  Vec::from_elem(cols + 1, 0u),
+                                  Vec::new(), Vec::new())
+        }
+    }
+
+    /// Builds a matrix from (row, column, value) triplets. Duplicate
+    /// entries are summed.
+    pub fn from_triplets(rows: uint, cols: uint, entries: &[(uint, uint, T)])
+        -> CscMatrix<T>
+    {
+        let swapped: Vec<(uint, uint, T)> = entries.iter()
+            .map(|&(i, j, ref x)| (j, i, x.clone()))
+            .collect();
+        CscMatrix {
+            data: Compressed::from_triplets(cols, rows, swapped.as_slice())
+        }
+    }
+
+    /// Converts to Compressed Sparse Row format.
+    pub fn to_csr(&self) -> CsrMatrix<T> {
+        CsrMatrix { data: self.data.transpose() }
+    }
+}
+
+impl<T: Semiring> BasicMatrix<T> for CsrMatrix<T> {
+    #[inline(always)]
+    fn get(&self, i: uint, j: uint) -> T { self.data.get(i, j) }
+    #[inline(always)]
+    fn set(&mut self, i: uint, j: uint, x: T) { self.data.set(i, j, x) }
+
+    fn num_rows(&self) -> uint { self.data.outer }
+    fn num_cols(&self) -> uint { self.data.inner }
+}
+
+impl<T: Semiring> BasicMatrix<T> for CscMatrix<T> {
+    #[inline(always)]
+    fn get(&self, i: uint, j: uint) -> T { self.data.get(j, i) }
+    #[inline(always)]
+    fn set(&mut self, i: uint, j: uint, x: T) { self.data.set(j, i, x) }
+
+    fn num_rows(&self) -> uint { self.data.inner }
+    fn num_cols(&self) -> uint { self.data.outer }
+}
+
+// Creating a sparse matrix only stores the nonzero entries, so
+// `algorithms::convert` turns a dense matrix into a sparse one.
+impl<T: Semiring> Create<T> for CsrMatrix<T> {
+    fn create(i: uint, j: uint, init: |uint, uint| -> T) -> CsrMatrix<T> {
+        CsrMatrix { data: Compressed::create(i, j, init) }
+    }
+}
+
+impl<T: Semiring> Create<T> for CscMatrix<T> {
+    fn create(i: uint, j: uint, init: |uint, uint| -> T) -> CscMatrix<T> {
+        CscMatrix { data: Compressed::create(j, i, |c, r| init(r, c)) }
+    }
+}
+
+impl<T: Semiring> CompressedMatrix<T> for CsrMatrix<T> {
+    fn is_row_major(&self) -> bool { true }
+
+    fn outer_dim(&self) -> uint { self.data.outer }
+    fn inner_dim(&self) -> uint { self.data.inner }
+
+    fn outer_ptr<'a>(&'a self) -> &'a [uint] { self.data.ptr.as_slice() }
+    fn inner_indices<'a>(&'a self) -> &'a [uint] { self.data.idx.as_slice() }
+    fn values<'a>(&'a self) -> &'a [T] { self.data.vals.as_slice() }
+
+    fn from_parts(outer: uint, inner: uint,
+                  ptr: Vec<uint>, idx: Vec<uint>, vals: Vec<T>)
+        -> CsrMatrix<T>
+    {
+        CsrMatrix { data: Compressed::new(outer, inner, ptr, idx, vals) }
+    }
+}
+
+impl<T: Semiring> CompressedMatrix<T> for CscMatrix<T> {
+    fn is_row_major(&self) -> bool { false }
+
+    fn outer_dim(&self) -> uint { self.data.outer }
+    fn inner_dim(&self) -> uint { self.data.inner }
+
+    fn outer_ptr<'a>(&'a self) -> &'a [uint] { self.data.ptr.as_slice() }
+    fn inner_indices<'a>(&'a self) -> &'a [uint] { self.data.idx.as_slice() }
+    fn values<'a>(&'a self) -> &'a [T] { self.data.vals.as_slice() }
+
+    fn from_parts(outer: uint, inner: uint,
+                  ptr: Vec<uint>, idx: Vec<uint>, vals: Vec<T>)
+        -> CscMatrix<T>
+    {
+        CscMatrix { data: Compressed::new(outer, inner, ptr, idx, vals) }
+    }
+}
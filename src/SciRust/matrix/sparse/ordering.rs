@@ -0,0 +1,250 @@
+// Fill and bandwidth reducing orderings for sparse matrices.
+//
+// Orderings are returned as a permutation vector `perm`, where
+// `perm[k]` is the original index that ends up in position k.
+
+#![allow(uppercase_variables)]
+
+use std::collections::PriorityQueue;
+
+use matrix::sparse::CompressedMatrix;
+
+// The graph of a square matrix's symmetrized pattern, A + At, without
+// self loops. Neighbors are sorted.
+pub fn adjacency<T, M: CompressedMatrix<T>>(A: &M)
+    -> Vec<Vec<uint>>
+{
+    assert!(A.num_rows() == A.num_cols());
+    let n = A.outer_dim();
+    let ptr = A.outer_ptr();
+    let idx = A.inner_indices();
+
+    let mut adj = Vec::from_fn(n, |_| Vec::new());
+    for i in range(0, n) {
+        for p in range(ptr[i], ptr[i + 1]) {
+            let j = idx[p];
+            if i != j {
+                adj.get_mut(i).push(j);
+                adj.get_mut(j).push(i);
+            }
+        }
+    }
+    for a in adj.mut_iter() {
+        a.sort();
+        a.dedup();
+    }
+    adj
+}
+
+// Returns the inverse of a permutation.
+pub fn invert_perm(perm: &[uint]) -> Vec<uint> {
+    let mut pinv = Vec::from_elem(perm.len(), 0u);
+    for (k, &i) in perm.iter().enumerate() {
+        *pinv.get_mut(i) = k;
+    }
+    pinv
+}
+
+// Approximate minimum degree ordering.
+//
+// This eliminates nodes on the quotient graph, where each eliminated
+// node becomes an element standing for the clique it created. The
+// degree of a variable is approximated from above by the number of
+// its remaining variable neighbors plus the sizes of its adjacent
+// elements, which avoids forming the filled graph explicitly.
+pub fn amd<T, M: CompressedMatrix<T>>(A: &M) -> Vec<uint> {
+    let n = A.num_rows();
+
+    // Variable neighbors, adjacent elements and, for eliminated nodes,
+    // the variables of the element.
+    let mut vars = adjacency(A);
+    let mut elems: Vec<Vec<uint>> = Vec::from_fn(n, |_| Vec::new());
+    let mut elem_vars: Vec<Vec<uint>> = Vec::from_fn(n, |_| Vec::new());
+
+    let mut eliminated = Vec::from_elem(n, false);
+    let mut degree: Vec<uint> = vars.iter().map(|a| a.len()).collect();
+    let mut mark = Vec::from_elem(n, n);
+
+    // The queue holds (n - degree, node) so the maximum is a node of
+    // minimum degree. Stale entries are skipped when popped.
+    let mut queue = PriorityQueue::new();
+    for i in range(0, n) {
+        queue.push((n - *degree.get(i), i));
+    }
+
+    let mut perm = Vec::with_capacity(n);
+    while perm.len() < n {
+        let (d, p) = queue.pop().unwrap();
+        if *eliminated.get(p) || n - d != *degree.get(p) {
+            continue
+        }
+        *eliminated.get_mut(p) = true;
+        perm.push(p);
+
+        // Lp is the union of p's variables and the variables of every
+        // element adjacent to p. Those elements are absorbed into p.
+        let mut Lp = Vec::new();
+        *mark.get_mut(p) = p;
+        for &i in vars.get(p).iter() {
+            if *mark.get(i) != p {
+                *mark.get_mut(i) = p;
+                Lp.push(i);
+            }
+        }
+        let absorbed = elems.get(p).clone();
+        for &e in absorbed.iter() {
+            for &i in elem_vars.get(e).iter() {
+                if i != p && *mark.get(i) != p {
+                    *mark.get_mut(i) = p;
+                    Lp.push(i);
+                }
+            }
+            elem_vars.get_mut(e).clear();
+        }
+        vars.get_mut(p).clear();
+        elems.get_mut(p).clear();
+
+        let remaining = n - perm.len();
+        for &i in Lp.iter() {
+            // Edges inside Lp are now represented by the element p.
+            vars.get_mut(i).retain(|&j| j != p && *mark.get(j) != p);
+            elems.get_mut(i).retain(|e| !absorbed.contains(e));
+            elems.get_mut(i).push(p);
+
+            let mut d = vars.get(i).len();
+            for &e in elems.get(i).iter() {
+                let size = if e == p { Lp.len() } else { elem_vars.get(e).len() };
+                d += size - 1;
+            }
+            let d = if d < remaining { d } else { remaining - 1 };
+            *degree.get_mut(i) = d;
+            queue.push((n - d, i));
+        }
+
+        *elem_vars.get_mut(p) = Lp;
+    }
+
+    perm
+}
+
+// Breadth first search from `root`, visiting neighbors in order of
+// increasing degree. Appends the visited nodes to `order` and returns
+// the number of levels and the index in `order` where the last level
+// starts.
+fn bfs_levels(adj: &[Vec<uint>], root: uint,
+              visited: &mut [bool], order: &mut Vec<uint>) -> (uint, uint) {
+    visited[root] = true;
+    order.push(root);
+
+    let mut depth = 0;
+    let mut level = order.len() - 1;
+    let mut head = level;
+    while head < order.len() {
+        depth += 1;
+        level = head;
+        let end = order.len();
+        while head < end {
+            let i = *order.get(head);
+            head += 1;
+
+            let mut next: Vec<uint> = adj[i].iter()
+                .map(|&j| j)
+                .filter(|&j| !visited[j])
+                .collect();
+            next.sort_by(|a, b| adj[*a].len().cmp(&adj[*b].len()));
+            for &j in next.iter() {
+                visited[j] = true;
+                order.push(j);
+            }
+        }
+    }
+    (depth, level)
+}
+
+// Finds a pseudo-peripheral node in the component containing `root`
+// with the George-Liu heuristic. Repeatedly restarts the search from a
+// minimum degree node in the last level until the number of levels
+// stops growing.
+fn pseudo_peripheral(adj: &[Vec<uint>], root: uint) -> uint {
+    let n = adj.len();
+    let mut root = root;
+    let mut depth = 0;
+    loop {
+        let mut visited = Vec::from_elem(n, false);
+        let mut order = Vec::new();
+        let (levels, last) = bfs_levels(adj, root, visited.as_mut_slice(),
+                                         &mut order);
+        if levels <= depth {
+            return root
+        }
+        depth = levels;
+
+        let mut best = *order.get(last);
+        for &i in order.slice_from(last).iter() {
+            if adj[i].len() < adj[best].len() {
+                best = i
+            }
+        }
+        if best == root {
+            return root
+        }
+        root = best;
+    }
+}
+
+// Reverse Cuthill-McKee ordering of the symmetrized pattern of A.
+// Returns the permutation together with the bandwidth and profile of
+// the permuted matrix.
+pub fn rcm<T, M: CompressedMatrix<T>>(A: &M)
+    -> (Vec<uint>, uint, uint)
+{
+    let adj = adjacency(A);
+    let n = adj.len();
+
+    let mut visited = Vec::from_elem(n, false);
+    let mut perm = Vec::with_capacity(n);
+
+    // Start each component at a minimum degree node.
+    let mut nodes: Vec<uint> = range(0, n).collect();
+    nodes.sort_by(|a, b| adj.get(*a).len().cmp(&adj.get(*b).len()));
+    for &i in nodes.iter() {
+        if !*visited.get(i) {
+            let root = pseudo_peripheral(adj.as_slice(), i);
+            bfs_levels(adj.as_slice(), root, visited.as_mut_slice(), &mut perm);
+        }
+    }
+    perm.reverse();
+
+    let (bandwidth, profile) = bandwidth_profile(A, perm.as_slice());
+    (perm, bandwidth, profile)
+}
+
+// The bandwidth and profile of P A Pt, where P is given by `perm`.
+// The bandwidth is the largest |i - j| over the nonzeros, and the
+// profile is the sum over rows of the distance from the first nonzero
+// to the diagonal.
+pub fn bandwidth_profile<T, M: CompressedMatrix<T>>
+(A: &M, perm: &[uint]) -> (uint, uint)
+{
+    let adj = adjacency(A);
+    let pinv = invert_perm(perm);
+
+    let mut bandwidth = 0;
+    let mut profile = 0;
+    for (i, a) in adj.iter().enumerate() {
+        let pi = *pinv.get(i);
+        let mut first = pi;
+        for &j in a.iter() {
+            let pj = *pinv.get(j);
+            if pj < first {
+                first = pj
+            }
+            let d = if pi > pj { pi - pj } else { pj - pi };
+            if d > bandwidth {
+                bandwidth = d
+            }
+        }
+        profile += pi - first;
+    }
+    (bandwidth, profile)
+}
@@ -0,0 +1,54 @@
+use std::cmp;
+use std::sync::Arc;
+use std::sync::Future;
+
+use matrix::Semiring;
+use matrix::sparse::CompressedMatrix;
+use matrix::sparse::algorithms::gustavson_lines;
+
+// Parallel Gustavson SpGEMM. The compressed lines of the result are
+// split into chunks of at most this many lines, each computed by its
+// own task; only the last chunk can be shorter.
+static CHUNK_SIZE: uint = 1 << 8;
+
+pub fn mat_mul<T: Semiring + Send + Share, M: CompressedMatrix<T> + Send + Share>
+(lhs: &Arc<M>, rhs: &Arc<M>) -> M {
+    assert!(lhs.num_cols() == rhs.num_rows());
+
+    // See algorithms::mat_mul for why CSC swaps the operands.
+    let swap = !lhs.is_row_major();
+    let (x, y) = if swap { (rhs.clone(), lhs.clone()) }
+                 else    { (lhs.clone(), rhs.clone()) };
+
+    let outer = x.outer_dim();
+    let inner = y.inner_dim();
+
+    let mut futures = Vec::new();
+    let mut lo = 0;
+    while lo < outer {
+        let hi = cmp::min(lo + CHUNK_SIZE, outer);
+        let xc = x.clone();
+        let yc = y.clone();
+        futures.push(Future::spawn(proc() {
+            gustavson_lines(&*xc, &*yc, lo, hi, swap)
+        }));
+        lo = hi;
+    }
+
+    // Stitch the chunks together, rebasing each chunk's offsets.
+    let mut ptr = Vec::with_capacity(outer + 1);
+    let mut idx = Vec::new();
+    let mut vals = Vec::new();
+    ptr.push(0);
+    for f in futures.move_iter() {
+        let (p, i, v) = f.unwrap();
+        let base = idx.len();
+        for &k in p.slice_from(1).iter() {
+            ptr.push(base + k);
+        }
+        idx.push_all_move(i);
+        vals.push_all_move(v);
+    }
+
+    CompressedMatrix::from_parts(outer, inner, ptr, idx, vals)
+}
@@ -0,0 +1,135 @@
+// Symmetric matrices in packed storage.
+
+#![allow(uppercase_variables)]
+
+use matrix::{BasicMatrix, Create, Semiring};
+
+// A symmetric matrix storing only its lower triangle, packed row by
+// row. Entry (i, j) with j <= i lives at data[i * (i + 1) / 2 + j], and
+// (i, j) and (j, i) always name the same slot.
+#[deriving(Clone)]
+pub struct SymmetricMatrix<T> {
+    n: uint,
+
+    data: Vec<T>
+}
+
+#[inline(always)]
+fn packed_index(i: uint, j: uint) -> uint {
+    if j <= i { i * (i + 1) / 2 + j } else { j * (j + 1) / 2 + i }
+}
+
+impl<T: Clone> SymmetricMatrix<T> {
+    /// Returns the packed lower triangle.
+    pub fn packed<'a>(&'a self) -> &'a [T] { self.data.as_slice() }
+}
+
+impl<T: Clone> BasicMatrix<T> for SymmetricMatrix<T> {
+    #[inline(always)]
+    fn get(&self, i: uint, j: uint) -> T {
+        if i < self.n && j < self.n {
+            self.data.as_slice()[packed_index(i, j)].clone()
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (self.n, self.n)))
+        }
+    }
+
+    // Setting (i, j) also sets (j, i).
+    #[inline(always)]
+    fn set(&mut self, i: uint, j: uint, x: T) {
+        if i < self.n && j < self.n {
+            self.data.as_mut_slice()[packed_index(i, j)] = x
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (self.n, self.n)))
+        }
+    }
+
+    fn num_rows(&self) -> uint { self.n }
+    fn num_cols(&self) -> uint { self.n }
+}
+
+// Only the lower triangle of `init` is evaluated, so converting a dense
+// matrix keeps its lower half.
+impl<T: Clone> Create<T> for SymmetricMatrix<T> {
+    fn create(i: uint, j: uint, init: |uint, uint| -> T) -> SymmetricMatrix<T> {
+        assert!(i == j);
+        let mut data = Vec::with_capacity(i * (i + 1) / 2);
+        for r in range(0, i) {
+            for c in range(0, r + 1) {
+                data.push(init(r, c));
+            }
+        }
+        SymmetricMatrix { n: i, data: data }
+    }
+}
+
+// Symmetric matrix multiply, A B with A symmetric. Each packed entry of
+// A is read once and applied to both of the rows it stands for.
+pub fn symm<T: Semiring, RHS: BasicMatrix<T>, Res: BasicMatrix<T> + Create<T>>
+(A: &SymmetricMatrix<T>, B: &RHS) -> Res
+{
+    if A.num_cols() != B.num_rows() {
+        fail!(format!("Incompatible matrix sizes. LHS: {:?}, RHS: {:?}",
+                   (A.num_rows(), A.num_cols()),
+                   (B.num_rows(), B.num_cols())))
+    }
+
+    let n = A.n;
+    let m = B.num_cols();
+    let mut acc: Vec<T> = Vec::from_elem(n * m, Semiring::zero());
+    let mut k = 0;
+    for i in range(0, n) {
+        for j in range(0, i + 1) {
+            let a = A.data.get(k).clone();
+            k += 1;
+            for c in range(0, m) {
+                let x = *acc.get(i * m + c) + a * B.get(j, c);
+                *acc.get_mut(i * m + c) = x;
+                if i != j {
+                    let y = *acc.get(j * m + c) + a * B.get(i, c);
+                    *acc.get_mut(j * m + c) = y;
+                }
+            }
+        }
+    }
+
+    Create::<T>::create(n, m, |i, j| acc.get(i * m + j).clone())
+}
+
+// Symmetric matrix multiply from the right, B A with A symmetric.
+pub fn symm_right<T: Semiring, LHS: BasicMatrix<T>, Res: BasicMatrix<T> + Create<T>>
+(B: &LHS, A: &SymmetricMatrix<T>) -> Res
+{
+    if B.num_cols() != A.num_rows() {
+        fail!(format!("Incompatible matrix sizes. LHS: {:?}, RHS: {:?}",
+                   (B.num_rows(), B.num_cols()),
+                   (A.num_rows(), A.num_cols())))
+    }
+
+    let n = A.n;
+    let m = B.num_rows();
+    let mut acc: Vec<T> = Vec::from_elem(m * n, Semiring::zero());
+    let mut k = 0;
+    for i in range(0, n) {
+        for j in range(0, i + 1) {
+            let a = A.data.get(k).clone();
+            k += 1;
+            for r in range(0, m) {
+                let x = *acc.get(r * n + j) + B.get(r, i) * a;
+                *acc.get_mut(r * n + j) = x;
+                if i != j {
+                    let y = *acc.get(r * n + i) + B.get(r, j) * a;
+                    *acc.get_mut(r * n + i) = y;
+                }
+            }
+        }
+    }
+
+    Create::<T>::create(m, n, |i, j| acc.get(i * n + j).clone())
+}
@@ -0,0 +1,246 @@
+// Reverse mode automatic differentiation over whole matrices.
+//
+// Operations are recorded on a Tape as they run, each node holding its
+// value. backward then walks the tape once in reverse, applying a hand
+// written adjoint for each matrix operation, so the cost is a small
+// multiple of the forward pass however many inputs there are. Compare
+// scalar::dual, which takes one pass per input.
+
+#![allow(uppercase_variables)]
+
+use matrix::{BasicMatrix, Create, Matrix};
+use matrix::algorithms::{mat_mul, mat_add, mat_sub, transpose, solve,
+                         cholesky_seq_inplace};
+use matrix::generate::{identity, zero_matrix};
+use matrix::triangular::{lower, upper};
+
+// A handle to a node on a Tape.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Var(uint);
+
+enum Op {
+    Input,
+    MatMul(uint, uint),
+    Add(uint, uint),
+    Transpose(uint),
+    Cholesky(uint),
+    Solve(uint, uint),
+    LogDet(uint)
+}
+
+struct Node {
+    op: Op,
+    value: Matrix<f64>
+}
+
+pub struct Tape {
+    nodes: Vec<Node>
+}
+
+// The adjoints of every node of a tape, from Tape::backward.
+pub struct Adjoints {
+    bars: Vec<Matrix<f64>>
+}
+
+impl Adjoints {
+    /// The gradient of the output with respect to v.
+    pub fn get<'a>(&'a self, v: Var) -> &'a Matrix<f64> {
+        let Var(i) = v;
+        self.bars.get(i)
+    }
+}
+
+// The 1 x 1 matrix holding x.
+fn scalar(x: f64) -> Matrix<f64> {
+    Create::create(1, 1, |_, _| x)
+}
+
+// The symmetric part, (A + A^T) / 2.
+fn symmetrize(A: &Matrix<f64>) -> Matrix<f64> {
+    Create::create(A.num_rows(), A.num_cols(), |i, j| 0.5 * (A.get(i, j) + A.get(j, i)))
+}
+
+// The inverse of A = L L^T, as L^-T L^-1.
+fn cholesky_inverse(L: &Matrix<f64>) -> Matrix<f64> {
+    let I: Matrix<f64> = identity(L.num_rows());
+    let Li: Matrix<f64> = solve(&lower(L), &I);
+    let Lit: Matrix<f64> = transpose(&Li);
+    mat_mul(&Lit, &Li)
+}
+
+impl Tape {
+    pub fn new() -> Tape {
+        Tape { nodes: Vec::new() }
+    }
+
+    fn push(&mut self, op: Op, value: Matrix<f64>) -> Var {
+        self.nodes.push(Node { op: op, value: value });
+        Var(self.nodes.len() - 1)
+    }
+
+    /// The value computed for v.
+    pub fn value<'a>(&'a self, v: Var) -> &'a Matrix<f64> {
+        let Var(i) = v;
+        &self.nodes.get(i).value
+    }
+
+    /// Records an independent variable.
+    pub fn input<M: BasicMatrix<f64>>(&mut self, A: &M) -> Var {
+        let value = Create::create(A.num_rows(), A.num_cols(), |i, j| A.get(i, j));
+        self.push(Input, value)
+    }
+
+    pub fn mat_mul(&mut self, a: Var, b: Var) -> Var {
+        let (Var(i), Var(j)) = (a, b);
+        let value = mat_mul(self.value(a), self.value(b));
+        self.push(MatMul(i, j), value)
+    }
+
+    pub fn add(&mut self, a: Var, b: Var) -> Var {
+        let (Var(i), Var(j)) = (a, b);
+        let value = mat_add(self.value(a), self.value(b));
+        self.push(Add(i, j), value)
+    }
+
+    pub fn transpose(&mut self, a: Var) -> Var {
+        let Var(i) = a;
+        let value = transpose(self.value(a));
+        self.push(Transpose(i), value)
+    }
+
+    /// The lower triangular L with L L^T = A, for symmetric positive
+    /// definite A.
+    pub fn cholesky(&mut self, a: Var) -> Var {
+        let Var(i) = a;
+        let mut L = self.value(a).clone();
+        cholesky_seq_inplace(&mut L);
+        self.push(Cholesky(i), L)
+    }
+
+    /// X with A X = B.
+    pub fn solve(&mut self, a: Var, b: Var) -> Var {
+        let (Var(i), Var(j)) = (a, b);
+        let value = solve(self.value(a), self.value(b));
+        self.push(Solve(i, j), value)
+    }
+
+    /// log det A as a 1 x 1 matrix, for symmetric positive definite A.
+    pub fn log_det(&mut self, a: Var) -> Var {
+        let Var(i) = a;
+        let mut L = self.value(a).clone();
+        cholesky_seq_inplace(&mut L);
+        let mut s = 0.0;
+        for k in range(0, L.num_rows()) {
+            s += 2.0 * L.get(k, k).ln();
+        }
+        self.push(LogDet(i), scalar(s))
+    }
+
+    /// Back-propagates from the 1 x 1 output `out`, returning the
+    /// gradient of out with respect to every node recorded before it.
+    pub fn backward(&self, out: Var) -> Adjoints {
+        let Var(o) = out;
+        {
+            let v = self.value(out);
+            assert!(v.num_rows() == 1 && v.num_cols() == 1);
+        }
+
+        let mut bars: Vec<Option<Matrix<f64>>> = Vec::from_fn(self.nodes.len(), |_| None);
+        *bars.get_mut(o) = Some(scalar(1.0));
+
+        for k in range(0, o + 1).rev() {
+            let bar = match *bars.get(k) {
+                Some(ref bar) => bar.clone(),
+                None => continue
+            };
+            let node = self.nodes.get(k);
+            match node.op {
+                Input => {}
+
+                // C = A B: Abar = Cbar B^T, Bbar = A^T Cbar.
+                MatMul(i, j) => {
+                    let Bt: Matrix<f64> = transpose(&self.nodes.get(j).value);
+                    let At: Matrix<f64> = transpose(&self.nodes.get(i).value);
+                    accumulate(&mut bars, i, mat_mul(&bar, &Bt));
+                    accumulate(&mut bars, j, mat_mul(&At, &bar));
+                }
+
+                Add(i, j) => {
+                    accumulate(&mut bars, i, bar.clone());
+                    accumulate(&mut bars, j, bar);
+                }
+
+                Transpose(i) => {
+                    accumulate(&mut bars, i, transpose(&bar));
+                }
+
+                // With Phi the lower triangle with its diagonal halved,
+                // Abar = L^-T Phi(L^T Lbar) L^-1, symmetrized since
+                // only symmetric perturbations of A make sense.
+                Cholesky(i) => {
+                    let L = &node.value;
+                    let Lt: Matrix<f64> = transpose(L);
+                    let mut P: Matrix<f64> = mat_mul(&Lt, &bar);
+                    for r in range(0, P.num_rows()) {
+                        for c in range(r, P.num_cols()) {
+                            let x = if r == c { 0.5 * P.get(r, c) } else { 0.0 };
+                            P.set(r, c, x);
+                        }
+                    }
+                    let Y: Matrix<f64> = solve(&upper(&Lt), &P);
+                    let Yt: Matrix<f64> = transpose(&Y);
+                    let St: Matrix<f64> = solve(&upper(&Lt), &Yt);
+                    accumulate(&mut bars, i, symmetrize(&St));
+                }
+
+                // X = A^-1 B: with S = A^-T Xbar, Bbar = S and
+                // Abar = -S X^T.
+                Solve(i, j) => {
+                    let At: Matrix<f64> = transpose(&self.nodes.get(i).value);
+                    let S: Matrix<f64> = solve(&At, &bar);
+                    let Xt: Matrix<f64> = transpose(&node.value);
+                    let SXt: Matrix<f64> = mat_mul(&S, &Xt);
+                    let zero: Matrix<f64> = zero_matrix(SXt.num_rows(), SXt.num_cols());
+                    accumulate(&mut bars, i, mat_sub(&zero, &SXt));
+                    accumulate(&mut bars, j, S);
+                }
+
+                // d log det A = tr(A^-1 dA), so Abar = cbar A^-1.
+                LogDet(i) => {
+                    let mut L = self.nodes.get(i).value.clone();
+                    cholesky_seq_inplace(&mut L);
+                    let mut Ai = cholesky_inverse(&L);
+                    let c = bar.get(0, 0);
+                    for r in range(0, Ai.num_rows()) {
+                        for s in range(0, Ai.num_cols()) {
+                            let x = Ai.get(r, s);
+                            Ai.set(r, s, c * x);
+                        }
+                    }
+                    accumulate(&mut bars, i, Ai);
+                }
+            }
+        }
+
+        let nodes = &self.nodes;
+        Adjoints {
+            bars: bars.move_iter().enumerate().map(|(k, bar)| {
+                match bar {
+                    Some(bar) => bar,
+                    None => {
+                        let v = &nodes.get(k).value;
+                        zero_matrix(v.num_rows(), v.num_cols())
+                    }
+                }
+            }).collect()
+        }
+    }
+}
+
+fn accumulate(bars: &mut Vec<Option<Matrix<f64>>>, i: uint, x: Matrix<f64>) {
+    let sum = match *bars.get(i) {
+        Some(ref bar) => mat_add(bar, &x),
+        None => x
+    };
+    *bars.get_mut(i) = Some(sum);
+}
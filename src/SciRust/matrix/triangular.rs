@@ -0,0 +1,308 @@
+// Triangular matrices, and the triangular code paths that the generic
+// algorithms dispatch to when BasicMatrix::triangle reports one.
+
+#![allow(uppercase_variables)]
+
+use std::cmp;
+use std::mem;
+
+use matrix::{BasicMatrix, Create, Triangle, Lower, Upper,
+             Semiring, Field};
+use matrix::generate::zero_matrix;
+
+// A lower triangular matrix. The triangle is packed row by row, so
+// entry (i, j) with j <= i lives at data[i * (i + 1) / 2 + j].
+#[deriving(Clone)]
+pub struct LowerTriangular<T> {
+    n: uint,
+
+    data: Vec<T>
+}
+
+// An upper triangular matrix. The triangle is packed row by row, so
+// row i starts at data[i * (2 * n - i + 1) / 2].
+#[deriving(Clone)]
+pub struct UpperTriangular<T> {
+    n: uint,
+
+    data: Vec<T>
+}
+
+fn structure_error(t: Triangle, i: uint, j: uint) -> ! {
+    fail!(format!("Cannot set {:?} outside the {} triangle.", (i, j), t))
+}
+
+impl<T: Semiring> BasicMatrix<T> for LowerTriangular<T> {
+    #[inline(always)]
+    fn get(&self, i: uint, j: uint) -> T {
+        if i < self.n && j < self.n {
+            if j <= i {
+                self.data.as_slice()[i * (i + 1) / 2 + j].clone()
+            }
+            else {
+                Semiring::zero()
+            }
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (self.n, self.n)))
+        }
+    }
+
+    #[inline(always)]
+    fn set(&mut self, i: uint, j: uint, x: T) {
+        if i < self.n && j < self.n {
+            if j <= i {
+                self.data.as_mut_slice()[i * (i + 1) / 2 + j] = x
+            }
+            else if !x.is_zero() {
+                structure_error(Lower, i, j)
+            }
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (self.n, self.n)))
+        }
+    }
+
+    fn num_rows(&self) -> uint { self.n }
+    fn num_cols(&self) -> uint { self.n }
+
+    fn triangle(&self) -> Option<Triangle> { Some(Lower) }
+}
+
+impl<T: Semiring> BasicMatrix<T> for UpperTriangular<T> {
+    #[inline(always)]
+    fn get(&self, i: uint, j: uint) -> T {
+        if i < self.n && j < self.n {
+            if j >= i {
+                let k = i * (2 * self.n - i + 1) / 2 + j - i;
+                self.data.as_slice()[k].clone()
+            }
+            else {
+                Semiring::zero()
+            }
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (self.n, self.n)))
+        }
+    }
+
+    #[inline(always)]
+    fn set(&mut self, i: uint, j: uint, x: T) {
+        if i < self.n && j < self.n {
+            if j >= i {
+                let k = i * (2 * self.n - i + 1) / 2 + j - i;
+                self.data.as_mut_slice()[k] = x
+            }
+            else if !x.is_zero() {
+                structure_error(Upper, i, j)
+            }
+        }
+        else {
+            fail!(format!("Index out of bounds. Index: {:?}, Dimension: {:?}",
+                       (i, j),
+                       (self.n, self.n)))
+        }
+    }
+
+    fn num_rows(&self) -> uint { self.n }
+    fn num_cols(&self) -> uint { self.n }
+
+    fn triangle(&self) -> Option<Triangle> { Some(Upper) }
+}
+
+// Creating a triangular matrix only evaluates `init` inside the
+// triangle, so converting a dense matrix drops the other half.
+impl<T: Semiring> Create<T> for LowerTriangular<T> {
+    fn create(i: uint, j: uint, init: |uint, uint| -> T) -> LowerTriangular<T> {
+        assert!(i == j);
+        let mut data = Vec::with_capacity(i * (i + 1) / 2);
+        for r in range(0, i) {
+            for c in range(0, r + 1) {
+                data.push(init(r, c));
+            }
+        }
+        LowerTriangular { n: i, data: data }
+    }
+}
+
+impl<T: Semiring> Create<T> for UpperTriangular<T> {
+    fn create(i: uint, j: uint, init: |uint, uint| -> T) -> UpperTriangular<T> {
+        assert!(i == j);
+        let mut data = Vec::with_capacity(i * (i + 1) / 2);
+        for r in range(0, i) {
+            for c in range(r, i) {
+                data.push(init(r, c));
+            }
+        }
+        UpperTriangular { n: i, data: data }
+    }
+}
+
+// A view of one triangle of a square matrix. Entries outside the
+// triangle read as zero, and only zero may be written there.
+pub struct TriangularView<'r, T, M> {
+    tri: Triangle,
+    base: &'r M
+}
+
+pub fn lower<'r, T, M: BasicMatrix<T>>(m: &'r M) -> TriangularView<'r, T, M> {
+    assert!(m.num_rows() == m.num_cols());
+    TriangularView { tri: Lower, base: m }
+}
+
+pub fn upper<'r, T, M: BasicMatrix<T>>(m: &'r M) -> TriangularView<'r, T, M> {
+    assert!(m.num_rows() == m.num_cols());
+    TriangularView { tri: Upper, base: m }
+}
+
+impl<'r, T, M> TriangularView<'r, T, M> {
+    #[inline(always)]
+    fn inside(&self, i: uint, j: uint) -> bool {
+        match self.tri {
+            Lower => j <= i,
+            Upper => j >= i
+        }
+    }
+
+    fn get_mut(&mut self) -> &'r mut M {
+        unsafe { mem::transmute(self.base) }
+    }
+}
+
+impl<'r, T: Semiring, M: BasicMatrix<T>> BasicMatrix<T> for TriangularView<'r, T, M> {
+    fn num_rows(&self) -> uint { self.base.num_rows() }
+    fn num_cols(&self) -> uint { self.base.num_cols() }
+
+    #[inline(always)]
+    fn get(&self, i: uint, j: uint) -> T {
+        if self.inside(i, j) {
+            self.base.get(i, j)
+        }
+        else {
+            Semiring::zero()
+        }
+    }
+
+    #[inline(always)]
+    fn set(&mut self, i: uint, j: uint, x: T) {
+        if self.inside(i, j) {
+            self.get_mut().set(i, j, x)
+        }
+        else if !x.is_zero() {
+            structure_error(self.tri, i, j)
+        }
+    }
+
+    fn triangle(&self) -> Option<Triangle> { Some(self.tri) }
+}
+
+// The columns of row i of an n column matrix that can be nonzero.
+#[inline(always)]
+fn row_range(t: Option<Triangle>, i: uint, n: uint) -> (uint, uint) {
+    match t {
+        Some(Lower) => (0, cmp::min(i + 1, n)),
+        Some(Upper) => (i, n),
+        None => (0, n)
+    }
+}
+
+// The rows of column j of an n row matrix that can be nonzero.
+#[inline(always)]
+fn col_range(t: Option<Triangle>, j: uint, n: uint) -> (uint, uint) {
+    match t {
+        Some(Lower) => (j, n),
+        Some(Upper) => (0, cmp::min(j + 1, n)),
+        None => (0, n)
+    }
+}
+
+// Matrix multiply where either side may be triangular. The inner sum
+// only runs over the indices where both factors can be nonzero.
+pub fn mat_mul<T: Semiring, LHS: BasicMatrix<T>, RHS: BasicMatrix<T>, Res: BasicMatrix<T> + Create<T>>
+(lhs: &LHS, rhs: &RHS) -> Res
+{
+    if lhs.num_cols() != rhs.num_rows() {
+        fail!(format!("Incompatible matrix sizes. LHS: {:?}, RHS: {:?}",
+                   (lhs.num_rows(), lhs.num_cols()),
+                   (rhs.num_rows(), rhs.num_cols())))
+    }
+
+    let n = lhs.num_cols();
+    let lt = lhs.triangle();
+    let rt = rhs.triangle();
+    Create::<T>::create(lhs.num_rows(), rhs.num_cols(), |i, j| {
+        let (a, b) = row_range(lt, i, n);
+        let (c, d) = col_range(rt, j, n);
+        let mut acc: T = Semiring::zero();
+        for k in range(cmp::max(a, c), cmp::min(b, d)) {
+            acc = acc + lhs.get(i, k) * rhs.get(k, j)
+        }
+        acc
+    })
+}
+
+// Solves A X = B for triangular A by forward or back substitution.
+pub fn solve<T: Field, M: BasicMatrix<T>, RHS: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>
+(A: &M, B: &RHS) -> R
+{
+    assert!(A.num_rows() == A.num_cols());
+    assert!(A.num_rows() == B.num_rows());
+
+    let n = A.num_rows();
+    let mut X: R = zero_matrix(n, B.num_cols());
+    match A.triangle() {
+        Some(Lower) => {
+            for i in range(0, n) {
+                substitute_row(A, B, &mut X, i, 0, i);
+            }
+        }
+        Some(Upper) => {
+            for i in range(0, n).rev() {
+                substitute_row(A, B, &mut X, i, i + 1, n);
+            }
+        }
+        None => fail!("triangular::solve requires a triangular matrix.")
+    }
+    X
+}
+
+// Computes row i of X from the rows lo to hi that are already solved.
+fn substitute_row<T: Field, M: BasicMatrix<T>, RHS: BasicMatrix<T>, R: BasicMatrix<T>>
+(A: &M, B: &RHS, X: &mut R, i: uint, lo: uint, hi: uint)
+{
+    for c in range(0, B.num_cols()) {
+        let mut x = B.get(i, c);
+        for k in range(lo, hi) {
+            x = x - A.get(i, k) * X.get(k, c);
+        }
+        X.set(i, c, x / A.get(i, i));
+    }
+}
+
+// The determinant of a triangular matrix is the product of its
+// diagonal.
+pub fn determinant<T: Semiring, M: BasicMatrix<T>>(A: &M) -> T {
+    assert!(A.num_rows() == A.num_cols());
+
+    let mut det: T = Semiring::one();
+    for i in range(0, A.num_rows()) {
+        det = det * A.get(i, i);
+    }
+    det
+}
+
+// The inverse of a triangular matrix, which has the same structure.
+pub fn inverse<T: Field, M: BasicMatrix<T>, R: BasicMatrix<T> + Create<T>>
+(A: &M) -> R
+{
+    let I: R = Create::<T>::create(A.num_rows(), A.num_rows(), |i, j| {
+        if i == j { Semiring::one() } else { Semiring::zero() }
+    });
+    solve(A, &I)
+}
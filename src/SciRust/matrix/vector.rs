@@ -0,0 +1,161 @@
+// An owned dense vector and BLAS level 1 style operations on it.
+
+use std::ops;
+
+use matrix::{BasicMatrix, Create, Vector, Semiring, Ring, Field, RealField};
+use matrix::algorithms;
+
+#[deriving(Clone, PartialEq, Show)]
+pub struct DenseVector<T> {
+    data: Vec<T>
+}
+
+impl<T: Clone> DenseVector<T> {
+    pub fn from_vec(data: Vec<T>) -> DenseVector<T> {
+        DenseVector { data: data }
+    }
+
+    pub fn from_fn(n: uint, f: |uint| -> T) -> DenseVector<T> {
+        DenseVector { data: Vec::from_fn(n, f) }
+    }
+
+    /// Copies any vector, including row and column views of a matrix.
+    pub fn from_vector<V: Vector<T>>(v: &V) -> DenseVector<T> {
+        DenseVector::from_fn(v.len(), |i| v.get(i))
+    }
+
+    /// Copies a matrix with a single row or a single column.
+    pub fn from_matrix<M: BasicMatrix<T>>(m: &M) -> DenseVector<T> {
+        if m.num_cols() == 1 {
+            DenseVector::from_fn(m.num_rows(), |i| m.get(i, 0))
+        }
+        else if m.num_rows() == 1 {
+            DenseVector::from_fn(m.num_cols(), |j| m.get(0, j))
+        }
+        else {
+            fail!(format!("Cannot convert a {:?} matrix to a vector.",
+                          (m.num_rows(), m.num_cols())))
+        }
+    }
+
+    pub fn as_slice<'a>(&'a self) -> &'a [T] { self.data.as_slice() }
+    pub fn as_mut_slice<'a>(&'a mut self) -> &'a mut [T] { self.data.as_mut_slice() }
+    pub fn unwrap(self) -> Vec<T> { self.data }
+}
+
+impl<T: Semiring> DenseVector<T> {
+    pub fn zeros(n: uint) -> DenseVector<T> {
+        DenseVector { data: Vec::from_elem(n, Semiring::zero()) }
+    }
+}
+
+impl<T: Clone> Vector<T> for DenseVector<T> {
+    fn len(&self) -> uint { self.data.len() }
+    #[inline(always)]
+    fn get(&self, i: uint) -> T { self.data.as_slice()[i].clone() }
+    #[inline(always)]
+    fn set(&mut self, i: uint, x: T) { self.data.as_mut_slice()[i] = x }
+}
+
+impl<T: Clone> ops::Index<uint, T> for DenseVector<T> {
+    #[inline(always)]
+    fn index(&self, i: &uint) -> T { self.get(*i) }
+}
+
+// Elementwise arithmetic.
+fn zip_with<T: Clone>(x: &DenseVector<T>, y: &DenseVector<T>, f: |&T, &T| -> T)
+    -> DenseVector<T>
+{
+    if x.len() != y.len() {
+        fail!(format!("Incompatible vector lengths. LHS: {:?}, RHS: {:?}",
+                      x.len(), y.len()))
+    }
+    DenseVector {
+        data: x.data.iter().zip(y.data.iter()).map(|(a, b)| f(a, b)).collect()
+    }
+}
+
+impl<T: Semiring> ops::Add<DenseVector<T>, DenseVector<T>> for DenseVector<T> {
+    fn add(&self, rhs: &DenseVector<T>) -> DenseVector<T> {
+        zip_with(self, rhs, |a, b| *a + *b)
+    }
+}
+
+impl<T: Ring> ops::Sub<DenseVector<T>, DenseVector<T>> for DenseVector<T> {
+    fn sub(&self, rhs: &DenseVector<T>) -> DenseVector<T> {
+        zip_with(self, rhs, |a, b| *a - *b)
+    }
+}
+
+impl<T: Ring> ops::Neg<DenseVector<T>> for DenseVector<T> {
+    fn neg(&self) -> DenseVector<T> {
+        DenseVector { data: self.data.iter().map(|a| -*a).collect() }
+    }
+}
+
+// Elementwise product.
+pub fn elem_mul<T: Semiring>(x: &DenseVector<T>, y: &DenseVector<T>) -> DenseVector<T> {
+    zip_with(x, y, |a, b| *a * *b)
+}
+
+// Elementwise quotient.
+pub fn elem_div<T: Field>(x: &DenseVector<T>, y: &DenseVector<T>) -> DenseVector<T> {
+    zip_with(x, y, |a, b| *a / *b)
+}
+
+// y = alpha x + y
+pub fn axpy<T: Semiring, V: Vector<T>>(alpha: T, x: &V, y: &mut DenseVector<T>) {
+    assert!(x.len() == y.len());
+    for i in range(0, y.len()) {
+        let yi = y.get(i);
+        y.set(i, alpha * x.get(i) + yi);
+    }
+}
+
+// x = alpha x
+pub fn scal<T: Semiring>(alpha: T, x: &mut DenseVector<T>) {
+    for xi in x.data.mut_iter() {
+        *xi = alpha * *xi;
+    }
+}
+
+pub fn dot<T: Semiring>(x: &DenseVector<T>, y: &DenseVector<T>) -> T {
+    algorithms::dot(x, y)
+}
+
+// The sum of absolute values.
+pub fn norm1<T: RealField, V: Vector<T>>(x: &V) -> T {
+    range(0, x.len()).fold(Semiring::zero(), |acc: T, i| acc + x.get(i).abs())
+}
+
+// The Euclidean norm. This scales by the largest entry to avoid
+// overflow and underflow in the sum of squares.
+pub fn norm2<T: RealField, V: Vector<T>>(x: &V) -> T {
+    let scale = norm_inf(x);
+    if scale.is_zero() {
+        return scale
+    }
+    let sum = range(0, x.len()).fold(Semiring::zero(), |acc: T, i| {
+        let y = x.get(i) / scale;
+        acc + y * y
+    });
+    scale * sum.sqrt()
+}
+
+// The largest absolute value.
+pub fn norm_inf<T: RealField, V: Vector<T>>(x: &V) -> T {
+    range(0, x.len()).fold(Semiring::zero(), |acc: T, i| {
+        let a = x.get(i).abs();
+        if a > acc { a } else { acc }
+    })
+}
+
+// The vector as an n x 1 matrix.
+pub fn column_matrix<T: Clone, R: BasicMatrix<T> + Create<T>>(x: &DenseVector<T>) -> R {
+    Create::<T>::create(x.len(), 1, |i, _| x.get(i))
+}
+
+// The vector as a 1 x n matrix.
+pub fn row_matrix<T: Clone, R: BasicMatrix<T> + Create<T>>(x: &DenseVector<T>) -> R {
+    Create::<T>::create(1, x.len(), |_, j| x.get(j))
+}
@@ -0,0 +1,486 @@
+// Arbitrary precision integers.
+//
+// The magnitude is stored in base 2^32, least significant digit first.
+// Multiplication is schoolbook and division is Knuth's Algorithm D,
+// which is plenty for the matrix sizes exact arithmetic is used on.
+
+use std::fmt;
+use std::from_str::FromStr;
+use std::ops;
+
+use scalar::{Semiring, Ring, EuclideanRing};
+
+// Both fields are kept normalized, so that derived equality is value
+// equality: the magnitude has no high zero digits, and the sign is 0
+// exactly when the magnitude is empty.
+#[deriving(Clone, PartialEq, Eq)]
+pub struct BigInt {
+    sign: i8,
+    mag: Vec<u32>
+}
+
+static BASE: u64 = 1 << 32;
+
+fn trim(v: &mut Vec<u32>) {
+    while v.len() > 0 && *v.get(v.len() - 1) == 0 {
+        v.pop();
+    }
+}
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+    if a.len() != b.len() {
+        return a.len().cmp(&b.len())
+    }
+    for i in range(0, a.len()).rev() {
+        if a[i] != b[i] {
+            return a[i].cmp(&b[i])
+        }
+    }
+    Equal
+}
+
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut r = Vec::with_capacity(a.len() + 1);
+    let mut carry = 0u64;
+    for i in range(0, a.len()) {
+        let t = a[i] as u64 + if i < b.len() { b[i] as u64 } else { 0 } + carry;
+        r.push(t as u32);
+        carry = t >> 32;
+    }
+    if carry > 0 {
+        r.push(carry as u32);
+    }
+    r
+}
+
+// a - b, for a >= b.
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut r = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for i in range(0, a.len()) {
+        let t = a[i] as i64 - if i < b.len() { b[i] as i64 } else { 0 } - borrow;
+        r.push(t as u32);
+        borrow = if t < 0 { 1 } else { 0 };
+    }
+    trim(&mut r);
+    r
+}
+
+fn mul_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new()
+    }
+    let mut r = Vec::from_elem(a.len() + b.len(), 0u32);
+    {
+        let rs = r.as_mut_slice();
+        for i in range(0, a.len()) {
+            let ai = a[i] as u64;
+            let mut carry = 0u64;
+            for j in range(0, b.len()) {
+                let t = ai * b[j] as u64 + rs[i + j] as u64 + carry;
+                rs[i + j] = t as u32;
+                carry = t >> 32;
+            }
+            rs[i + b.len()] = carry as u32;
+        }
+    }
+    trim(&mut r);
+    r
+}
+
+fn divrem_small(a: &[u32], d: u32) -> (Vec<u32>, u32) {
+    let mut q = Vec::from_elem(a.len(), 0u32);
+    let mut r = 0u64;
+    for i in range(0, a.len()).rev() {
+        let cur = (r << 32) | a[i] as u64;
+        *q.get_mut(i) = (cur / d as u64) as u32;
+        r = cur % d as u64;
+    }
+    trim(&mut q);
+    (q, r as u32)
+}
+
+// a << s for 0 <= s < 32, with `extra` high digits of room.
+fn shl_digits(a: &[u32], s: uint, extra: uint) -> Vec<u32> {
+    let mut r = Vec::from_elem(a.len() + extra, 0u32);
+    {
+        let rs = r.as_mut_slice();
+        for i in range(0, a.len()) {
+            rs[i] = rs[i] | (a[i] << s);
+            if s > 0 && i + 1 < rs.len() {
+                rs[i + 1] = a[i] >> (32 - s);
+            }
+        }
+    }
+    r
+}
+
+// Knuth's Algorithm D, as laid out in Hacker's Delight. Returns the
+// quotient and remainder of magnitudes, with v nonempty.
+fn divrem_mag(u: &[u32], v: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if cmp_mag(u, v) == Less {
+        return (Vec::new(), Vec::from_slice(u))
+    }
+    if v.len() == 1 {
+        let (q, r) = divrem_small(u, v[0]);
+        return (q, if r == 0 { Vec::new() } else { vec!(r) })
+    }
+
+    let n = v.len();
+    let m = u.len() - n;
+
+    // Shift so the top digit of the divisor has its high bit set, which
+    // keeps each estimated quotient digit within two of the truth.
+    let mut s = 0;
+    while (v[n - 1] << s) & 0x8000_0000 == 0 {
+        s += 1;
+    }
+    let vn = shl_digits(v, s, 0);
+    let vn = vn.as_slice();
+    let mut un = shl_digits(u, s, 1);
+    let un = un.as_mut_slice();
+
+    let mut q = Vec::from_elem(m + 1, 0u32);
+    for j in range(0, m + 1).rev() {
+        let num = (un[j + n] as u64 << 32) | un[j + n - 1] as u64;
+        let mut qhat = num / vn[n - 1] as u64;
+        let mut rhat = num % vn[n - 1] as u64;
+        while qhat >= BASE || qhat * vn[n - 2] as u64 > ((rhat << 32) | un[j + n - 2] as u64) {
+            qhat -= 1;
+            rhat += vn[n - 1] as u64;
+            if rhat >= BASE {
+                break
+            }
+        }
+
+        // un[j .. j + n] -= qhat * vn
+        let mut borrow = 0i64;
+        let mut carry = 0u64;
+        for i in range(0, n) {
+            let p = qhat * vn[i] as u64 + carry;
+            carry = p >> 32;
+            let t = un[i + j] as i64 - borrow - (p & 0xffff_ffff) as i64;
+            un[i + j] = t as u32;
+            borrow = if t < 0 { 1 } else { 0 };
+        }
+        let t = un[j + n] as i64 - borrow - carry as i64;
+        un[j + n] = t as u32;
+
+        // The estimate was one too large, so add a divisor back.
+        if t < 0 {
+            qhat -= 1;
+            let mut c = 0u64;
+            for i in range(0, n) {
+                let t = un[i + j] as u64 + vn[i] as u64 + c;
+                un[i + j] = t as u32;
+                c = t >> 32;
+            }
+            un[j + n] = (un[j + n] as u64 + c) as u32;
+        }
+        *q.get_mut(j) = qhat as u32;
+    }
+    trim(&mut q);
+
+    let mut r = Vec::from_elem(n, 0u32);
+    for i in range(0, n) {
+        *r.get_mut(i) = if s == 0 { un[i] } else { (un[i] >> s) | (un[i + 1] << (32 - s)) };
+    }
+    trim(&mut r);
+    (q, r)
+}
+
+impl BigInt {
+    fn from_parts(sign: i8, mut mag: Vec<u32>) -> BigInt {
+        trim(&mut mag);
+        BigInt { sign: if mag.is_empty() { 0 } else { sign }, mag: mag }
+    }
+
+    pub fn from_i64(n: i64) -> BigInt {
+        // -(n + 1) + 1 keeps i64::MIN in range.
+        let m = if n < 0 { (-(n + 1)) as u64 + 1 } else { n as u64 };
+        BigInt::from_parts(if n < 0 { -1 } else { 1 }, vec!(m as u32, (m >> 32) as u32))
+    }
+
+    /// Returns the value as an i64, or None if it does not fit.
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.mag.len() > 2 {
+            return None
+        }
+        let mut m = 0u64;
+        for (i, &d) in self.mag.iter().enumerate() {
+            m = m | (d as u64 << (32 * i));
+        }
+        if self.sign >= 0 {
+            if m <= 0x7fff_ffff_ffff_ffff { Some(m as i64) } else { None }
+        }
+        else if m <= 0x8000_0000_0000_0000 {
+            Some(-((m - 1) as i64) - 1)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Returns -1, 0 or 1.
+    pub fn signum(&self) -> int { self.sign as int }
+
+    pub fn is_negative(&self) -> bool { self.sign < 0 }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt { sign: self.sign * self.sign, mag: self.mag.clone() }
+    }
+
+    /// Truncating division: the quotient rounds toward zero and the
+    /// remainder takes the sign of self, as for machine integers.
+    pub fn div_rem(&self, other: &BigInt) -> (BigInt, BigInt) {
+        if other.sign == 0 {
+            fail!("BigInt division by zero.")
+        }
+        let (q, r) = divrem_mag(self.mag.as_slice(), other.mag.as_slice());
+        (BigInt::from_parts(self.sign * other.sign, q),
+         BigInt::from_parts(self.sign, r))
+    }
+}
+
+// The nonnegative greatest common divisor. gcd(0, 0) is 0.
+pub fn gcd(a: &BigInt, b: &BigInt) -> BigInt {
+    let mut a = a.abs();
+    let mut b = b.abs();
+    while b.sign != 0 {
+        let (_, r) = a.div_rem(&b);
+        a = b;
+        b = r;
+    }
+    a
+}
+
+impl ops::Add<BigInt, BigInt> for BigInt {
+    fn add(&self, rhs: &BigInt) -> BigInt {
+        if self.sign == 0 {
+            return rhs.clone()
+        }
+        if rhs.sign == 0 {
+            return self.clone()
+        }
+        let (a, b) = (self.mag.as_slice(), rhs.mag.as_slice());
+        if self.sign == rhs.sign {
+            return BigInt::from_parts(self.sign, add_mag(a, b))
+        }
+        match cmp_mag(a, b) {
+            Equal => BigInt::from_parts(0, Vec::new()),
+            Greater => BigInt::from_parts(self.sign, sub_mag(a, b)),
+            Less => BigInt::from_parts(rhs.sign, sub_mag(b, a))
+        }
+    }
+}
+
+impl ops::Sub<BigInt, BigInt> for BigInt {
+    fn sub(&self, rhs: &BigInt) -> BigInt {
+        *self + -*rhs
+    }
+}
+
+impl ops::Mul<BigInt, BigInt> for BigInt {
+    fn mul(&self, rhs: &BigInt) -> BigInt {
+        BigInt::from_parts(self.sign * rhs.sign,
+                           mul_mag(self.mag.as_slice(), rhs.mag.as_slice()))
+    }
+}
+
+impl ops::Div<BigInt, BigInt> for BigInt {
+    fn div(&self, rhs: &BigInt) -> BigInt {
+        let (q, _) = self.div_rem(rhs);
+        q
+    }
+}
+
+impl ops::Rem<BigInt, BigInt> for BigInt {
+    fn rem(&self, rhs: &BigInt) -> BigInt {
+        let (_, r) = self.div_rem(rhs);
+        r
+    }
+}
+
+impl ops::Neg<BigInt> for BigInt {
+    fn neg(&self) -> BigInt {
+        BigInt { sign: -self.sign, mag: self.mag.clone() }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        if self.sign != other.sign {
+            return self.sign.cmp(&other.sign)
+        }
+        let (a, b) = (self.mag.as_slice(), other.mag.as_slice());
+        match self.sign {
+            0 => Equal,
+            1 => cmp_mag(a, b),
+            _ => cmp_mag(b, a)
+        }
+    }
+}
+
+impl Semiring for BigInt {
+    fn zero() -> BigInt { BigInt::from_parts(0, Vec::new()) }
+    fn one() -> BigInt { BigInt::from_parts(1, vec!(1)) }
+}
+
+impl Ring for BigInt {}
+
+// BigInt arithmetic cannot overflow, so only division by zero gives
+// None.
+impl EuclideanRing for BigInt {
+    fn add_exact(&self, rhs: &BigInt) -> Option<BigInt> { Some(*self + *rhs) }
+    fn sub_exact(&self, rhs: &BigInt) -> Option<BigInt> { Some(*self - *rhs) }
+    fn mul_exact(&self, rhs: &BigInt) -> Option<BigInt> { Some(*self * *rhs) }
+    fn div_rem_exact(&self, rhs: &BigInt) -> Option<(BigInt, BigInt)> {
+        if rhs.sign == 0 { None } else { Some(self.div_rem(rhs)) }
+    }
+    fn neg_exact(&self) -> Option<BigInt> { Some(-*self) }
+}
+
+impl fmt::Show for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.sign == 0 {
+            return write!(f, "0")
+        }
+
+        // Peel off nine decimal digits at a time.
+        let mut chunks = Vec::new();
+        let mut m = self.mag.clone();
+        while !m.is_empty() {
+            let (q, r) = divrem_small(m.as_slice(), 1_000_000_000);
+            chunks.push(r);
+            m = q;
+        }
+
+        let mut s = String::new();
+        if self.sign < 0 {
+            s.push_str("-");
+        }
+        s.push_str(format!("{}", *chunks.get(chunks.len() - 1)).as_slice());
+        for c in chunks.slice_to(chunks.len() - 1).iter().rev() {
+            s.push_str(format!("{:09}", *c).as_slice());
+        }
+        write!(f, "{}", s)
+    }
+}
+
+// Parses an optional minus sign followed by decimal digits.
+impl FromStr for BigInt {
+    fn from_str(s: &str) -> Option<BigInt> {
+        let (sign, digits) = if s.starts_with("-") { (-1, s.slice_from(1)) } else { (1, s) };
+        if digits.is_empty() {
+            return None
+        }
+
+        let mut mag: Vec<u32> = Vec::new();
+        for c in digits.chars() {
+            let d = match c.to_digit(10) {
+                Some(d) => d as u64,
+                None => return None
+            };
+            // mag = 10 mag + d
+            let mut carry = d;
+            for x in mag.mut_iter() {
+                let t = *x as u64 * 10 + carry;
+                *x = t as u32;
+                carry = t >> 32;
+            }
+            if carry > 0 {
+                mag.push(carry as u32);
+            }
+        }
+        Some(BigInt::from_parts(sign, mag))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::i64;
+    use super::{BigInt, gcd};
+    use scalar::Semiring;
+
+    fn big(s: &str) -> BigInt {
+        from_str(s).unwrap()
+    }
+
+    // Checks u = q v + r with |r| < |v| as well as the expected values.
+    fn check_div(u: &str, v: &str, q: &str, r: &str) {
+        let (u, v) = (big(u), big(v));
+        let (qc, rc) = u.div_rem(&v);
+        assert_eq!(qc, big(q));
+        assert_eq!(rc, big(r));
+        assert_eq!(qc * v + rc, u);
+        assert!(rc.abs() < v.abs());
+    }
+
+    // These inputs make the corrected quotient digit estimate one too
+    // large, so Algorithm D has to add the divisor back. Each divisor
+    // has a zero second digit.
+    #[test]
+    fn test_div_add_back() {
+        check_div("36893488147419103232", "18446744073709551617",
+                  "1", "18446744073709551615");
+        check_div("39614081257132168796771975168", "36893488147419103234",
+                  "1073741823", "36893488145271619586");
+        check_div("170141183460469231731687303722326556671", "36893488147419103234",
+                  "4611686018427387903", "27670116117006778369");
+    }
+
+    // A divisor whose top digit already has its high bit set, so there
+    // is no normalization shift.
+    #[test]
+    fn test_div_add_back_unshifted() {
+        check_div("79228162514264337593543950336", "39614081257132168796771975169",
+                  "1", "39614081257132168796771975167");
+    }
+
+    #[test]
+    fn test_div_high_zero_digits() {
+        // 2^96 / 2^64 and 2^96 / (2^64 + 1).
+        check_div("79228162514264337593543950336", "18446744073709551616",
+                  "4294967296", "0");
+        check_div("79228162514264337593543950336", "18446744073709551617",
+                  "4294967295", "18446744069414584321");
+    }
+
+    #[test]
+    fn test_div_signs() {
+        check_div("-7", "2", "-3", "-1");
+        check_div("7", "-2", "-3", "1");
+        check_div("-7", "-2", "3", "-1");
+        check_div("3", "18446744073709551617", "0", "3");
+    }
+
+    #[test]
+    fn test_i64_round_trip() {
+        for &n in [i64::MIN, i64::MIN + 1, -1, 0, 1, i64::MAX].iter() {
+            let b = BigInt::from_i64(n);
+            assert_eq!(b.to_i64(), Some(n));
+            assert_eq!(format!("{}", b), format!("{}", n));
+            assert_eq!(big(format!("{}", n).as_slice()), b);
+        }
+        let one: BigInt = Semiring::one();
+        assert_eq!((BigInt::from_i64(i64::MIN) - one).to_i64(), None);
+        assert_eq!((BigInt::from_i64(i64::MAX) + one).to_i64(), None);
+        assert_eq!(-BigInt::from_i64(i64::MIN), big("9223372036854775808"));
+    }
+
+    #[test]
+    fn test_gcd_zero() {
+        let zero: BigInt = Semiring::zero();
+        assert_eq!(gcd(&zero, &zero), zero);
+        assert_eq!(gcd(&zero, &big("-12")), big("12"));
+        assert_eq!(gcd(&big("-12"), &zero), big("12"));
+        assert_eq!(gcd(&big("36893488147419103232"), &big("-12")), big("4"));
+    }
+}
@@ -0,0 +1,109 @@
+// Complex numbers.
+
+use std::num;
+use std::ops;
+
+use scalar::{Conjugate, Semiring, Ring, Field};
+
+#[deriving(Clone, PartialEq, Show)]
+pub struct Complex<T> {
+    pub re: T,
+    pub im: T
+}
+
+impl<T: Float> Complex<T> {
+    pub fn new(re: T, im: T) -> Complex<T> {
+        Complex { re: re, im: im }
+    }
+
+    pub fn from_polar(r: T, theta: T) -> Complex<T> {
+        Complex { re: r * theta.cos(), im: r * theta.sin() }
+    }
+
+    /// Returns |z|^2.
+    pub fn norm_sqr(&self) -> T {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// Returns |z|, without overflow in the intermediate squares.
+    pub fn norm(&self) -> T {
+        self.re.hypot(self.im)
+    }
+
+    pub fn arg(&self) -> T {
+        self.im.atan2(self.re)
+    }
+
+    /// The principal square root.
+    pub fn sqrt(&self) -> Complex<T> {
+        let two: T = num::one::<T>() + num::one();
+        let r = self.norm();
+        let re = ((r + self.re) / two).sqrt();
+        let im = ((r - self.re) / two).sqrt();
+        Complex { re: re, im: if self.im < num::zero() { -im } else { im } }
+    }
+}
+
+impl<T: Float> Conjugate for Complex<T> {
+    #[inline(always)]
+    fn conj(&self) -> Complex<T> {
+        Complex { re: self.re, im: -self.im }
+    }
+}
+
+impl<T: Float> ops::Add<Complex<T>, Complex<T>> for Complex<T> {
+    #[inline(always)]
+    fn add(&self, rhs: &Complex<T>) -> Complex<T> {
+        Complex { re: self.re + rhs.re, im: self.im + rhs.im }
+    }
+}
+
+impl<T: Float> ops::Sub<Complex<T>, Complex<T>> for Complex<T> {
+    #[inline(always)]
+    fn sub(&self, rhs: &Complex<T>) -> Complex<T> {
+        Complex { re: self.re - rhs.re, im: self.im - rhs.im }
+    }
+}
+
+impl<T: Float> ops::Mul<Complex<T>, Complex<T>> for Complex<T> {
+    #[inline(always)]
+    fn mul(&self, rhs: &Complex<T>) -> Complex<T> {
+        Complex { re: self.re * rhs.re - self.im * rhs.im,
+                  im: self.re * rhs.im + self.im * rhs.re }
+    }
+}
+
+// Smith's algorithm, which scales by the larger component of the
+// divisor to avoid overflow.
+impl<T: Float> ops::Div<Complex<T>, Complex<T>> for Complex<T> {
+    fn div(&self, rhs: &Complex<T>) -> Complex<T> {
+        if rhs.re.abs() >= rhs.im.abs() {
+            let r = rhs.im / rhs.re;
+            let d = rhs.re + rhs.im * r;
+            Complex { re: (self.re + self.im * r) / d,
+                      im: (self.im - self.re * r) / d }
+        }
+        else {
+            let r = rhs.re / rhs.im;
+            let d = rhs.re * r + rhs.im;
+            Complex { re: (self.re * r + self.im) / d,
+                      im: (self.im * r - self.re) / d }
+        }
+    }
+}
+
+impl<T: Float> ops::Neg<Complex<T>> for Complex<T> {
+    #[inline(always)]
+    fn neg(&self) -> Complex<T> {
+        Complex { re: -self.re, im: -self.im }
+    }
+}
+
+impl<T: Float> Semiring for Complex<T> {
+    fn zero() -> Complex<T> { Complex { re: num::zero(), im: num::zero() } }
+    fn one() -> Complex<T> { Complex { re: num::one(), im: num::zero() } }
+}
+
+impl<T: Float> Ring for Complex<T> {}
+
+impl<T: Float> Field for Complex<T> {}
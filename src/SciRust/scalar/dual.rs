@@ -0,0 +1,190 @@
+// Dual numbers a + b e with e^2 = 0, for forward mode automatic
+// differentiation.
+//
+// Evaluating f at re + 1 e gives f(re) + f'(re) e, so any algorithm
+// written over the hierarchy traits computes derivatives alongside
+// values when run over Dual. Dual<T> is a RealField, which covers
+// mat_mul, inverse, solve and the Cholesky factorizations. Equality,
+// is_zero and comparisons look at the value only, so pivoting follows
+// the undifferentiated computation.
+
+#![allow(uppercase_variables)]
+
+use std::num;
+use std::ops;
+
+use matrix::{BasicMatrix, Create, Matrix};
+use scalar::{Semiring, Ring, Field, RealField};
+
+#[deriving(Clone, Show)]
+pub struct Dual<T> {
+    pub re: T,
+    pub eps: T
+}
+
+impl<T: Float> Dual<T> {
+    pub fn new(re: T, eps: T) -> Dual<T> {
+        Dual { re: re, eps: eps }
+    }
+
+    /// A constant, with zero derivative.
+    pub fn constant(re: T) -> Dual<T> {
+        Dual { re: re, eps: num::zero() }
+    }
+
+    /// The independent variable, with unit derivative.
+    pub fn variable(re: T) -> Dual<T> {
+        Dual { re: re, eps: num::one() }
+    }
+
+    pub fn exp(&self) -> Dual<T> {
+        let e = self.re.exp();
+        Dual { re: e, eps: self.eps * e }
+    }
+
+    pub fn ln(&self) -> Dual<T> {
+        Dual { re: self.re.ln(), eps: self.eps / self.re }
+    }
+}
+
+impl<T: Float> ops::Add<Dual<T>, Dual<T>> for Dual<T> {
+    #[inline(always)]
+    fn add(&self, rhs: &Dual<T>) -> Dual<T> {
+        Dual { re: self.re + rhs.re, eps: self.eps + rhs.eps }
+    }
+}
+
+impl<T: Float> ops::Sub<Dual<T>, Dual<T>> for Dual<T> {
+    #[inline(always)]
+    fn sub(&self, rhs: &Dual<T>) -> Dual<T> {
+        Dual { re: self.re - rhs.re, eps: self.eps - rhs.eps }
+    }
+}
+
+impl<T: Float> ops::Mul<Dual<T>, Dual<T>> for Dual<T> {
+    #[inline(always)]
+    fn mul(&self, rhs: &Dual<T>) -> Dual<T> {
+        Dual { re: self.re * rhs.re, eps: self.re * rhs.eps + self.eps * rhs.re }
+    }
+}
+
+impl<T: Float> ops::Div<Dual<T>, Dual<T>> for Dual<T> {
+    #[inline(always)]
+    fn div(&self, rhs: &Dual<T>) -> Dual<T> {
+        Dual { re: self.re / rhs.re,
+               eps: (self.eps * rhs.re - self.re * rhs.eps) / (rhs.re * rhs.re) }
+    }
+}
+
+impl<T: Float> ops::Neg<Dual<T>> for Dual<T> {
+    #[inline(always)]
+    fn neg(&self) -> Dual<T> {
+        Dual { re: -self.re, eps: -self.eps }
+    }
+}
+
+// Like the ordering, equality looks at the value only, so that a zero
+// pivot with a nonzero derivative still counts as zero.
+impl<T: Float> PartialEq for Dual<T> {
+    fn eq(&self, other: &Dual<T>) -> bool {
+        self.re == other.re
+    }
+}
+
+impl<T: Float> PartialOrd for Dual<T> {
+    fn partial_cmp(&self, other: &Dual<T>) -> Option<Ordering> {
+        self.re.partial_cmp(&other.re)
+    }
+}
+
+impl<T: Float> Semiring for Dual<T> {
+    #[inline(always)]
+    fn zero() -> Dual<T> { Dual::constant(num::zero()) }
+    #[inline(always)]
+    fn one() -> Dual<T> { Dual::constant(num::one()) }
+
+    #[inline(always)]
+    fn is_zero(&self) -> bool { self.re == num::zero() }
+}
+
+impl<T: Float> Ring for Dual<T> {}
+
+impl<T: Float> Field for Dual<T> {}
+
+impl<T: Float> RealField for Dual<T> {
+    fn sqrt(&self) -> Dual<T> {
+        let s = self.re.sqrt();
+        let two: T = num::one::<T>() + num::one();
+        Dual { re: s, eps: self.eps / (two * s) }
+    }
+
+    fn abs(&self) -> Dual<T> {
+        if self.re < num::zero() { -*self } else { *self }
+    }
+}
+
+// Runs f on X with tangent dX, returning f(X) and its directional
+// derivative in the direction dX.
+pub fn pushforward<T: Float,
+                   M: BasicMatrix<T>,
+                   D: BasicMatrix<T>>(f: |&Matrix<Dual<T>>| -> Matrix<Dual<T>>,
+                                      X: &M, dX: &D) -> (Matrix<T>, Matrix<T>) {
+    assert!(X.num_rows() == dX.num_rows() && X.num_cols() == dX.num_cols());
+
+    let Xd: Matrix<Dual<T>> = Create::create(X.num_rows(), X.num_cols(), |i, j| {
+        Dual::new(X.get(i, j), dX.get(i, j))
+    });
+    let F = f(&Xd);
+    let V: Matrix<T> = Create::create(F.num_rows(), F.num_cols(), |i, j| F.get(i, j).re);
+    let dF: Matrix<T> = Create::create(F.num_rows(), F.num_cols(), |i, j| F.get(i, j).eps);
+    (V, dF)
+}
+
+// The Jacobian of a matrix valued function of a matrix, with both
+// flattened in row-major order: for an m x n input and a p x q output,
+// J(a q + b, i n + j) is dF(a, b) / dX(i, j). Takes one evaluation of f
+// per input entry.
+pub fn jacobian<T: Float,
+                M: BasicMatrix<T>,
+                R: BasicMatrix<T> + Create<T>>(f: |&Matrix<Dual<T>>| -> Matrix<Dual<T>>,
+                                               X: &M) -> R {
+    let m = X.num_rows();
+    let n = X.num_cols();
+    let mut Xd: Matrix<Dual<T>> = Create::create(m, n, |i, j| Dual::constant(X.get(i, j)));
+
+    let mut columns = Vec::with_capacity(m * n);
+    for i in range(0, m) {
+        for j in range(0, n) {
+            Xd.set(i, j, Dual::variable(X.get(i, j)));
+            let F = f(&Xd);
+            let q = F.num_cols();
+            let col: Vec<T> = Vec::from_fn(F.num_rows() * q, |k| F.get(k / q, k % q).eps);
+            columns.push(col);
+            Xd.set(i, j, Dual::constant(X.get(i, j)));
+        }
+    }
+
+    let p = if columns.len() > 0 { columns.get(0).len() } else { 0 };
+    Create::create(p, m * n, |a, b| *columns.get(b).get(a))
+}
+
+// The gradient of a scalar valued function of a matrix, as a matrix of
+// the same shape as X.
+pub fn gradient<T: Float,
+                M: BasicMatrix<T>,
+                R: BasicMatrix<T> + Create<T>>(f: |&Matrix<Dual<T>>| -> Dual<T>,
+                                               X: &M) -> R {
+    let m = X.num_rows();
+    let n = X.num_cols();
+    let mut Xd: Matrix<Dual<T>> = Create::create(m, n, |i, j| Dual::constant(X.get(i, j)));
+
+    let mut G: Vec<T> = Vec::with_capacity(m * n);
+    for i in range(0, m) {
+        for j in range(0, n) {
+            Xd.set(i, j, Dual::variable(X.get(i, j)));
+            G.push(f(&Xd).eps);
+            Xd.set(i, j, Dual::constant(X.get(i, j)));
+        }
+    }
+    Create::create(m, n, |i, j| *G.get(i * n + j))
+}
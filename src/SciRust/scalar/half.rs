@@ -0,0 +1,319 @@
+// 16 bit floating point formats, for storing large matrices compactly.
+//
+// F16 is IEEE 754 binary16 (5 exponent bits, 10 fraction bits) and BF16
+// is bfloat16 (8 exponent bits, 7 fraction bits, the range of f32).
+// Both are storage formats: arithmetic converts to a wider format,
+// operates and rounds back. When the wider format carries at least
+// twice the precision plus two bits, and the exact result is in its
+// normal range, the two roundings give the correctly rounded result of
+// +, -, *, / and sqrt. f32 qualifies for F16, whose results all land
+// well inside f32's normal range. BF16 shares f32's exponent range, so
+// its subnormal results would be subnormal in f32 as well and could be
+// rounded twice; it uses f64 instead.
+//
+// Long sums lose accuracy quickly at this precision, so products of
+// large matrices should use mat_mul_f32 or mat_mul_f64 below, which
+// read 16 bit inputs and accumulate in the wider format.
+
+#![allow(uppercase_variables)]
+
+use std::fmt;
+use std::mem;
+use std::ops;
+
+use matrix::{BasicMatrix, Create};
+use scalar::{Semiring, Ring, Field, RealField};
+
+// Rounds x to nearest, ties to even, in the binary format with the
+// given field widths, returning its bits.
+fn round_f64(x: f64, exp_bits: uint, man_bits: uint) -> u16 {
+    let bits = unsafe { mem::transmute::<f64, u64>(x) };
+    let sign = ((bits >> 63) << (exp_bits + man_bits)) as u16;
+    let exp = ((bits >> 52) & 0x7ff) as int;
+    let man = bits & ((1u64 << 52) - 1);
+
+    let bias = (1i << (exp_bits - 1)) - 1;
+    let inf = (((1u << exp_bits) - 1) << man_bits) as u16;
+
+    if exp == 0x7ff {
+        // Keep NaNs quiet.
+        return sign | inf | if man != 0 { 1 << (man_bits - 1) } else { 0 }
+    }
+    if exp == 0 {
+        // f64 zeros and subnormals are far below both formats.
+        return sign
+    }
+
+    let e = exp - 1023;
+    if e > bias {
+        return sign | inf
+    }
+
+    // The 53 bit significand, and how far to shift it right to leave
+    // the target's fraction bits, plus the implicit bit for normals.
+    let m = man | (1u64 << 52);
+    let emin = 1 - bias;
+    let shift = if e >= emin { 52 - man_bits } else { 52 - man_bits + (emin - e) as uint };
+    if shift > 53 {
+        return sign
+    }
+
+    let mut q = m >> shift;
+    let rem = m & ((1u64 << shift) - 1);
+    let half = 1u64 << (shift - 1);
+    if rem > half || (rem == half && q & 1 == 1) {
+        q += 1;
+    }
+
+    // For normals q includes the implicit bit, which carries into the
+    // exponent field, as does rounding up past the largest fraction.
+    let biased = if e >= emin { (e + bias - 1) as u64 } else { 0 };
+    sign | ((biased << man_bits) + q) as u16
+}
+
+// The exact value of the given bits in the binary format.
+fn widen(bits: u16, exp_bits: uint, man_bits: uint) -> f64 {
+    let negative = (bits >> (exp_bits + man_bits)) & 1 == 1;
+    let exp = ((bits >> man_bits) as uint) & ((1 << exp_bits) - 1);
+    let man = (bits as uint) & ((1 << man_bits) - 1);
+    let bias = (1i << (exp_bits - 1)) - 1;
+
+    let x: f64 = if exp == (1 << exp_bits) - 1 {
+        if man != 0 { Float::nan() } else { Float::infinity() }
+    }
+    else if exp == 0 {
+        (man as f64) * 2.0f64.powi((1 - bias - man_bits as int) as i32)
+    }
+    else {
+        (((1 << man_bits) + man) as f64) * 2.0f64.powi((exp as int - bias - man_bits as int) as i32)
+    };
+    if negative { -x } else { x }
+}
+
+macro_rules! half(
+    ($name:ident, $exp_bits:expr, $man_bits:expr, $to_wide:ident, $from_wide:ident) => (
+        #[deriving(Clone)]
+        pub struct $name {
+            bits: u16
+        }
+
+        impl $name {
+            /// The value with the given bit pattern.
+            pub fn from_bits(bits: u16) -> $name { $name { bits: bits } }
+            pub fn to_bits(&self) -> u16 { self.bits }
+
+            /// Rounds to nearest, ties to even.
+            pub fn from_f64(x: f64) -> $name {
+                $name { bits: round_f64(x, $exp_bits, $man_bits) }
+            }
+
+            /// Rounds to nearest, ties to even. Widening f32 to f64 is
+            /// exact, so this rounds only once.
+            pub fn from_f32(x: f32) -> $name {
+                $name::from_f64(x as f64)
+            }
+
+            /// Exact.
+            pub fn to_f64(&self) -> f64 { widen(self.bits, $exp_bits, $man_bits) }
+
+            /// Exact.
+            pub fn to_f32(&self) -> f32 { self.to_f64() as f32 }
+        }
+
+        // By value, so that -0 equals 0 and NaN equals nothing.
+        impl PartialEq for $name {
+            fn eq(&self, other: &$name) -> bool { self.to_f32() == other.to_f32() }
+        }
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &$name) -> Option<Ordering> {
+                self.to_f32().partial_cmp(&other.to_f32())
+            }
+        }
+
+        impl ops::Add<$name, $name> for $name {
+            fn add(&self, rhs: &$name) -> $name { $name::$from_wide(self.$to_wide() + rhs.$to_wide()) }
+        }
+
+        impl ops::Sub<$name, $name> for $name {
+            fn sub(&self, rhs: &$name) -> $name { $name::$from_wide(self.$to_wide() - rhs.$to_wide()) }
+        }
+
+        impl ops::Mul<$name, $name> for $name {
+            fn mul(&self, rhs: &$name) -> $name { $name::$from_wide(self.$to_wide() * rhs.$to_wide()) }
+        }
+
+        impl ops::Div<$name, $name> for $name {
+            fn div(&self, rhs: &$name) -> $name { $name::$from_wide(self.$to_wide() / rhs.$to_wide()) }
+        }
+
+        // Flipping the sign bit is exact.
+        impl ops::Neg<$name> for $name {
+            fn neg(&self) -> $name { $name { bits: self.bits ^ (1 << ($exp_bits + $man_bits)) } }
+        }
+
+        impl Semiring for $name {
+            fn zero() -> $name { $name { bits: 0 } }
+            fn one() -> $name { $name::from_f64(1.0) }
+        }
+
+        impl Ring for $name {}
+
+        impl Field for $name {}
+
+        impl RealField for $name {
+            fn sqrt(&self) -> $name { $name::$from_wide(self.$to_wide().sqrt()) }
+            fn abs(&self) -> $name { $name { bits: self.bits & !(1 << ($exp_bits + $man_bits)) } }
+        }
+
+        impl FloatFormat for $name {
+            fn from_double(x: f64) -> $name { $name::from_f64(x) }
+            fn to_double(&self) -> f64 { self.to_f64() }
+            fn to_single(&self) -> f32 { self.to_f32() }
+        }
+
+        impl fmt::Show for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", self.to_f32())
+            }
+        }
+    )
+)
+
+// Floating point formats, for converting matrices between precisions.
+// Widening to f64 is always exact.
+pub trait FloatFormat : Clone {
+    /// Rounds to nearest, ties to even.
+    fn from_double(x: f64) -> Self;
+    fn to_double(&self) -> f64;
+    fn to_single(&self) -> f32;
+}
+
+half!(F16, 5, 10, to_f32, from_f32)
+half!(BF16, 8, 7, to_f64, from_f64)
+
+impl FloatFormat for f32 {
+    fn from_double(x: f64) -> f32 { x as f32 }
+    fn to_double(&self) -> f64 { *self as f64 }
+    fn to_single(&self) -> f32 { *self }
+}
+
+impl FloatFormat for f64 {
+    fn from_double(x: f64) -> f64 { x }
+    fn to_double(&self) -> f64 { *self }
+    fn to_single(&self) -> f32 { *self as f32 }
+}
+
+// Converts a matrix between floating point formats, rounding once when
+// narrowing.
+pub fn convert_precision<S: FloatFormat,
+                         T: FloatFormat,
+                         M: BasicMatrix<S>,
+                         R: BasicMatrix<T> + Create<T>>(A: &M) -> R {
+    Create::<T>::create(A.num_rows(), A.num_cols(), |i, j| {
+        FloatFormat::from_double(A.get(i, j).to_double())
+    })
+}
+
+// The product of low precision matrices, with each element widened to
+// f32 on load and the sums accumulated in f32.
+pub fn mat_mul_f32<T: FloatFormat,
+                   LHS: BasicMatrix<T>,
+                   RHS: BasicMatrix<T>,
+                   Res: BasicMatrix<f32> + Create<f32>>(lhs: &LHS, rhs: &RHS) -> Res {
+    if lhs.num_cols() != rhs.num_rows() {
+        fail!(format!("Incompatible matrix sizes. LHS: {:?}, RHS: {:?}",
+                   (lhs.num_rows(), lhs.num_cols()),
+                   (rhs.num_rows(), rhs.num_cols())))
+    }
+
+    Create::<f32>::create(lhs.num_rows(), rhs.num_cols(), |i, j| {
+        let mut acc = 0.0f32;
+        for k in range(0, lhs.num_cols()) {
+            acc += lhs.get(i, k).to_single() * rhs.get(k, j).to_single();
+        }
+        acc
+    })
+}
+
+// As mat_mul_f32, accumulating in f64.
+pub fn mat_mul_f64<T: FloatFormat,
+                   LHS: BasicMatrix<T>,
+                   RHS: BasicMatrix<T>,
+                   Res: BasicMatrix<f64> + Create<f64>>(lhs: &LHS, rhs: &RHS) -> Res {
+    if lhs.num_cols() != rhs.num_rows() {
+        fail!(format!("Incompatible matrix sizes. LHS: {:?}, RHS: {:?}",
+                   (lhs.num_rows(), lhs.num_cols()),
+                   (rhs.num_rows(), rhs.num_cols())))
+    }
+
+    Create::<f64>::create(lhs.num_rows(), rhs.num_cols(), |i, j| {
+        let mut acc = 0.0f64;
+        for k in range(0, lhs.num_cols()) {
+            acc += lhs.get(i, k).to_double() * rhs.get(k, j).to_double();
+        }
+        acc
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::round_f64;
+
+    fn f16(x: f64) -> u16 { round_f64(x, 5, 10) }
+    fn bf16(x: f64) -> u16 { round_f64(x, 8, 7) }
+
+    fn pow2(e: i32) -> f64 { 2.0f64.powi(e) }
+
+    // Halfway cases go to the neighbour with an even last bit.
+    #[test]
+    fn test_ties_to_even() {
+        assert_eq!(f16(1.0 + pow2(-11)), 0x3c00);
+        assert_eq!(f16(1.0 + 3.0 * pow2(-11)), 0x3c02);
+        assert_eq!(bf16(1.0 + pow2(-8)), 0x3f80);
+        assert_eq!(bf16(1.0 + 3.0 * pow2(-8)), 0x3f82);
+    }
+
+    // Rounding up past the largest fraction carries into the exponent,
+    // and past the largest finite value gives infinity.
+    #[test]
+    fn test_overflow_to_inf() {
+        assert_eq!(f16(65504.0), 0x7bff);
+        assert_eq!(f16(65519.0), 0x7bff);
+        assert_eq!(f16(65520.0), 0x7c00);
+        assert_eq!(f16(-65520.0), 0xfc00);
+        assert_eq!(bf16((2.0 - pow2(-7)) * pow2(127)), 0x7f7f);
+        assert_eq!(bf16((2.0 - pow2(-8)) * pow2(127)), 0x7f80);
+    }
+
+    #[test]
+    fn test_subnormals() {
+        assert_eq!(f16(pow2(-24)), 0x0001);
+        assert_eq!(f16(3.0 * pow2(-25)), 0x0002);
+        assert_eq!(bf16(pow2(-133)), 0x0001);
+
+        // The largest subnormal rounding up to the smallest normal.
+        assert_eq!(f16(1023.5 * pow2(-24)), 0x0400);
+    }
+
+    // Half the smallest subnormal is a tie with zero, which is even;
+    // anything above it rounds up.
+    #[test]
+    fn test_underflow_to_zero() {
+        assert_eq!(f16(pow2(-25)), 0x0000);
+        assert_eq!(f16(-pow2(-25)), 0x8000);
+        assert_eq!(f16(pow2(-25) * (1.0 + pow2(-20))), 0x0001);
+        assert_eq!(f16(pow2(-26)), 0x0000);
+        assert_eq!(bf16(pow2(-134)), 0x0000);
+    }
+
+    #[test]
+    fn test_special_values() {
+        let inf: f64 = Float::infinity();
+        let nan: f64 = Float::nan();
+        assert_eq!(f16(inf), 0x7c00);
+        assert_eq!(f16(-inf), 0xfc00);
+        assert_eq!(f16(nan) & 0x7fff, 0x7e00);
+        assert_eq!(bf16(inf), 0x7f80);
+    }
+}
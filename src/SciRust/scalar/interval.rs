@@ -0,0 +1,301 @@
+// Interval arithmetic over f64, for computations with rigorous bounds.
+//
+// Every operation returns an interval containing all results of the
+// operation on points of its operands. There is no control over the
+// FPU rounding mode here, so each computed bound is instead moved one
+// ulp outward, which covers the half ulp rounding error of the
+// operation. Interval is a Field, so mat_mul, mat_add and the
+// triangular solves (algorithms::trsm, triangular::solve) run over it
+// unchanged and return enclosures. So does gauss_jordan_solve, which is
+// naive interval Gaussian elimination; verified_solve below usually
+// gives much tighter bounds.
+
+#![allow(uppercase_variables)]
+
+use std::fmt;
+use std::mem;
+use std::ops;
+
+use matrix::{BasicMatrix, Create, Matrix};
+use matrix::algorithms::{mat_mul, mat_add, mat_sub};
+use matrix::generate::identity;
+use scalar::{Semiring, Ring, Field};
+
+// The next f64 toward plus infinity.
+fn next_up(x: f64) -> f64 {
+    if x != x || x == Float::infinity() {
+        return x
+    }
+    if x == 0.0 {
+        // The smallest subnormal.
+        return unsafe { mem::transmute::<u64, f64>(1) }
+    }
+    let bits = unsafe { mem::transmute::<f64, u64>(x) };
+    let bits = if x > 0.0 { bits + 1 } else { bits - 1 };
+    unsafe { mem::transmute::<u64, f64>(bits) }
+}
+
+// The next f64 toward minus infinity.
+fn next_down(x: f64) -> f64 {
+    -next_up(-x)
+}
+
+fn fabs(x: f64) -> f64 {
+    if x < 0.0 { -x } else { x }
+}
+
+// A product in which zero times anything, even infinity, is zero, as
+// it is for the intervals an unbounded interval stands for.
+fn mul_bound(a: f64, b: f64) -> f64 {
+    if a == 0.0 || b == 0.0 { 0.0 } else { a * b }
+}
+
+// The closed interval [lo, hi]. Bounds may be infinite.
+#[deriving(Clone, PartialEq)]
+pub struct Interval {
+    lo: f64,
+    hi: f64
+}
+
+impl Interval {
+    pub fn new(lo: f64, hi: f64) -> Interval {
+        if !(lo <= hi) {
+            fail!(format!("Invalid interval [{}, {}].", lo, hi))
+        }
+        Interval { lo: lo, hi: hi }
+    }
+
+    /// The degenerate interval [x, x].
+    pub fn point(x: f64) -> Interval {
+        Interval::new(x, x)
+    }
+
+    /// The whole real line.
+    pub fn entire() -> Interval {
+        Interval { lo: Float::neg_infinity(), hi: Float::infinity() }
+    }
+
+    pub fn lo(&self) -> f64 { self.lo }
+    pub fn hi(&self) -> f64 { self.hi }
+
+    /// A point of the interval near its middle.
+    pub fn mid(&self) -> f64 { 0.5 * self.lo + 0.5 * self.hi }
+
+    /// An upper bound on the distance from mid() to either end.
+    pub fn rad(&self) -> f64 {
+        let m = self.mid();
+        next_up(if m - self.lo > self.hi - m { m - self.lo } else { self.hi - m })
+    }
+
+    pub fn contains(&self, x: f64) -> bool { self.lo <= x && x <= self.hi }
+
+    /// True if self lies in the interior of other.
+    pub fn is_interior(&self, other: &Interval) -> bool {
+        other.lo < self.lo && self.hi < other.hi
+    }
+
+    /// The smallest interval containing both.
+    pub fn hull(&self, other: &Interval) -> Interval {
+        Interval {
+            lo: if self.lo < other.lo { self.lo } else { other.lo },
+            hi: if self.hi > other.hi { self.hi } else { other.hi }
+        }
+    }
+
+    /// Widens each end by eps times the width, and by at least one
+    /// ulp, so that the result strictly contains self.
+    pub fn inflate(&self, eps: f64) -> Interval {
+        let r = eps * (self.hi - self.lo);
+        Interval { lo: next_down(self.lo - r), hi: next_up(self.hi + r) }
+    }
+}
+
+impl ops::Add<Interval, Interval> for Interval {
+    fn add(&self, rhs: &Interval) -> Interval {
+        Interval { lo: next_down(self.lo + rhs.lo), hi: next_up(self.hi + rhs.hi) }
+    }
+}
+
+impl ops::Sub<Interval, Interval> for Interval {
+    fn sub(&self, rhs: &Interval) -> Interval {
+        Interval { lo: next_down(self.lo - rhs.hi), hi: next_up(self.hi - rhs.lo) }
+    }
+}
+
+impl ops::Mul<Interval, Interval> for Interval {
+    fn mul(&self, rhs: &Interval) -> Interval {
+        let p = [mul_bound(self.lo, rhs.lo), mul_bound(self.lo, rhs.hi),
+                 mul_bound(self.hi, rhs.lo), mul_bound(self.hi, rhs.hi)];
+        let mut lo = p[0];
+        let mut hi = p[0];
+        for &x in p.slice_from(1).iter() {
+            if x < lo { lo = x }
+            if x > hi { hi = x }
+        }
+        Interval { lo: next_down(lo), hi: next_up(hi) }
+    }
+}
+
+// Division by an interval containing zero gives the whole line, which
+// is a valid, if useless, enclosure.
+impl ops::Div<Interval, Interval> for Interval {
+    fn div(&self, rhs: &Interval) -> Interval {
+        if rhs.contains(0.0) {
+            return Interval::entire()
+        }
+        let recip = Interval { lo: next_down(1.0 / rhs.hi), hi: next_up(1.0 / rhs.lo) };
+        *self * recip
+    }
+}
+
+impl ops::Neg<Interval> for Interval {
+    fn neg(&self) -> Interval {
+        Interval { lo: -self.hi, hi: -self.lo }
+    }
+}
+
+impl Semiring for Interval {
+    fn zero() -> Interval { Interval::point(0.0) }
+    fn one() -> Interval { Interval::point(1.0) }
+}
+
+impl Ring for Interval {}
+
+impl Field for Interval {}
+
+impl fmt::Show for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}, {}]", self.lo, self.hi)
+    }
+}
+
+// Converts a floating point matrix to degenerate intervals.
+pub fn point_matrix<M: BasicMatrix<f64>, R: BasicMatrix<Interval> + Create<Interval>>(A: &M) -> R {
+    Create::<Interval>::create(A.num_rows(), A.num_cols(), |i, j| {
+        Interval::point(A.get(i, j))
+    })
+}
+
+// x - x is NaN for infinities and NaN, and 0 otherwise.
+fn all_finite(A: &Matrix<f64>) -> bool {
+    range(0, A.num_rows()).all(|i| {
+        range(0, A.num_cols()).all(|j| {
+            let x = A.get(i, j);
+            x - x == 0.0
+        })
+    })
+}
+
+// An approximate inverse by Gauss-Jordan elimination with partial
+// pivoting, or None if a pivot vanishes. Only used as a preconditioner,
+// so its accuracy does not affect the validity of the bounds.
+fn approximate_inverse(A: &Matrix<f64>) -> Option<Matrix<f64>> {
+    let N = A.num_rows();
+    let mut U = A.clone();
+    let mut X: Matrix<f64> = identity(N);
+    for k in range(0, N) {
+        let mut p = k;
+        for i in range(k + 1, N) {
+            if fabs(U.get(i, k)) > fabs(U.get(p, k)) {
+                p = i;
+            }
+        }
+        if U.get(p, k) == 0.0 {
+            return None
+        }
+        for j in range(0, N) {
+            let (u, x) = (U.get(k, j), X.get(k, j));
+            U.set(k, j, U.get(p, j));
+            X.set(k, j, X.get(p, j));
+            U.set(p, j, u);
+            X.set(p, j, x);
+        }
+
+        let d = 1.0 / U.get(k, k);
+        for j in range(0, N) {
+            let (u, x) = (U.get(k, j), X.get(k, j));
+            U.set(k, j, u * d);
+            X.set(k, j, x * d);
+        }
+        for i in range(0, N) {
+            let f = U.get(i, k);
+            if i == k || f == 0.0 {
+                continue
+            }
+            for j in range(0, N) {
+                let (u, x) = (U.get(i, j), X.get(i, j));
+                U.set(i, j, u - f * U.get(k, j));
+                X.set(i, j, x - f * X.get(k, j));
+            }
+        }
+    }
+    Some(X)
+}
+
+// Encloses the solutions of A X = B for every A and B within the given
+// intervals, by the Krawczyk operator with epsilon inflation (Rump's
+// method). With R an approximate inverse of mid(A) and x an approximate
+// solution, the error E = X - x satisfies E = R (B - A x) + (I - R A) E.
+// Once an inflated guess is mapped into its own interior, Brouwer's
+// fixed point theorem proves that A is nonsingular and that x plus the
+// image encloses the solution. Returns None if that does not happen
+// within a few iterations, e.g. for singular or very ill conditioned A,
+// or if A or B has unbounded entries.
+pub fn verified_solve<M: BasicMatrix<Interval>,
+                      RHS: BasicMatrix<Interval>,
+                      R: BasicMatrix<Interval> + Create<Interval>>(A: &M, B: &RHS) -> Option<R> {
+    static MAX_ITER: uint = 15;
+    static EPS: f64 = 0.1;
+
+    assert!(A.num_rows() == A.num_cols());
+    assert!(A.num_rows() == B.num_rows());
+
+    let N = A.num_rows();
+    let W = B.num_cols();
+
+    // The approximations, in plain floating point.
+    let Am: Matrix<f64> = Create::create(N, N, |i, j| A.get(i, j).mid());
+    let Bm: Matrix<f64> = Create::create(N, W, |i, j| B.get(i, j).mid());
+    if !all_finite(&Am) {
+        return None
+    }
+    let Rm = match approximate_inverse(&Am) {
+        Some(Rm) => Rm,
+        None => return None
+    };
+    let Xm: Matrix<f64> = mat_mul(&Rm, &Bm);
+    // Unbounded entries, or an inverse that overflowed, leave nothing
+    // to build point intervals from.
+    if !all_finite(&Rm) || !all_finite(&Xm) {
+        return None
+    }
+
+    // Z = R (B - A x) and C = I - R A, enclosed.
+    let Ri: Matrix<Interval> = point_matrix(&Rm);
+    let X: Matrix<Interval> = point_matrix(&Xm);
+    let AX: Matrix<Interval> = mat_mul(A, &X);
+    let residual: Matrix<Interval> = mat_sub(B, &AX);
+    let Z: Matrix<Interval> = mat_mul(&Ri, &residual);
+    let RA: Matrix<Interval> = mat_mul(&Ri, A);
+    let I: Matrix<Interval> = identity(N);
+    let C: Matrix<Interval> = mat_sub(&I, &RA);
+
+    let mut E = Z.clone();
+    for _ in range(0, MAX_ITER) {
+        let Y: Matrix<Interval> = Create::create(N, W, |i, j| E.get(i, j).inflate(EPS));
+        let CY: Matrix<Interval> = mat_mul(&C, &Y);
+        let KY: Matrix<Interval> = mat_add(&Z, &CY);
+
+        let mut verified = true;
+        for i in range(0, N) {
+            for j in range(0, W) {
+                verified = verified && KY.get(i, j).is_interior(&Y.get(i, j));
+            }
+        }
+        if verified {
+            return Some(Create::create(N, W, |i, j| X.get(i, j) + KY.get(i, j)))
+        }
+        E = KY;
+    }
+    None
+}
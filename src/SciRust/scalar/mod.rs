@@ -0,0 +1,136 @@
+// Scalar types that can be used as matrix elements, and the algebraic
+// structures the algorithms ask of them.
+
+use std::num::{CheckedAdd, CheckedSub, CheckedMul, CheckedDiv};
+use std::ops;
+
+pub mod bigint;
+pub mod complex;
+pub mod dual;
+pub mod half;
+pub mod interval;
+pub mod modular;
+pub mod rational;
+pub mod tropical;
+
+// Each algorithm bounds its element type by the weakest structure it
+// needs: products and sums only need a Semiring, subtraction a Ring,
+// division a Field, and square roots a RealField.
+
+// Addition and multiplication with identities, where multiplication
+// distributes over addition. Neither operation need be invertible.
+pub trait Semiring : ops::Add<Self, Self> + ops::Mul<Self, Self> + Clone + PartialEq {
+    fn zero() -> Self;
+    fn one() -> Self;
+
+    fn is_zero(&self) -> bool { *self == Semiring::zero() }
+}
+
+// A semiring with additive inverses.
+pub trait Ring : Semiring + ops::Sub<Self, Self> + ops::Neg<Self> {}
+
+// A ring in which every nonzero element has a multiplicative inverse.
+pub trait Field : Ring + ops::Div<Self, Self> {}
+
+// An ordered field with square roots of its nonnegative elements.
+pub trait RealField : Field + PartialOrd {
+    fn sqrt(&self) -> Self;
+    fn abs(&self) -> Self;
+}
+
+// Integers, for the exact integer matrix algorithms in matrix::integer.
+// The _exact operations return None on overflow instead of wrapping, so
+// a fixed width result is either correct or missing.
+pub trait EuclideanRing : Ring + Ord {
+    fn add_exact(&self, rhs: &Self) -> Option<Self>;
+    fn sub_exact(&self, rhs: &Self) -> Option<Self>;
+    fn mul_exact(&self, rhs: &Self) -> Option<Self>;
+
+    /// Quotient and remainder, with the quotient rounded toward zero.
+    /// None for a zero divisor as well.
+    fn div_rem_exact(&self, rhs: &Self) -> Option<(Self, Self)>;
+
+    fn neg_exact(&self) -> Option<Self> {
+        let zero: Self = Semiring::zero();
+        zero.sub_exact(self)
+    }
+}
+
+macro_rules! primitive_semiring(
+    ($($t:ty),+) => (
+        $(
+            impl Semiring for $t {
+                #[inline(always)]
+                fn zero() -> $t { 0 as $t }
+                #[inline(always)]
+                fn one() -> $t { 1 as $t }
+            }
+        )+
+    )
+)
+
+macro_rules! primitive_ring(
+    ($($t:ty),+) => (
+        $(
+            impl Ring for $t {}
+        )+
+    )
+)
+
+macro_rules! primitive_real_field(
+    ($($t:ty),+) => (
+        $(
+            impl Field for $t {}
+
+            impl RealField for $t {
+                // By value, so that this calls Float::sqrt.
+                #[inline(always)]
+                fn sqrt(&self) -> $t { (*self).sqrt() }
+                #[inline(always)]
+                fn abs(&self) -> $t { if *self < 0 as $t { -*self } else { *self } }
+            }
+        )+
+    )
+)
+
+primitive_semiring!(f32, f64, int, i8, i16, i32, i64, uint, u8, u16, u32, u64)
+primitive_ring!(f32, f64, int, i8, i16, i32, i64)
+primitive_real_field!(f32, f64)
+
+macro_rules! primitive_euclidean_ring(
+    ($($t:ty),+) => (
+        $(
+            impl EuclideanRing for $t {
+                fn add_exact(&self, rhs: &$t) -> Option<$t> { self.checked_add(rhs) }
+                fn sub_exact(&self, rhs: &$t) -> Option<$t> { self.checked_sub(rhs) }
+                fn mul_exact(&self, rhs: &$t) -> Option<$t> { self.checked_mul(rhs) }
+
+                // checked_div is None for a zero divisor and for MIN / -1,
+                // the only cases where % can fail as well.
+                fn div_rem_exact(&self, rhs: &$t) -> Option<($t, $t)> {
+                    self.checked_div(rhs).map(|q| (q, *self % *rhs))
+                }
+            }
+        )+
+    )
+)
+
+primitive_euclidean_ring!(int, i32, i64)
+
+// Complex conjugation. Real types are their own conjugate.
+pub trait Conjugate {
+    fn conj(&self) -> Self;
+}
+
+macro_rules! real_conjugate(
+    ($($t:ty),+) => (
+        $(
+            impl Conjugate for $t {
+                #[inline(always)]
+                fn conj(&self) -> $t { *self }
+            }
+        )+
+    )
+)
+
+real_conjugate!(f32, f64, int, i8, i16, i32, i64, uint, u8, u16, u32, u64)
@@ -0,0 +1,153 @@
+// Integers modulo p.
+//
+// The modulus is part of the type, through a marker type implementing
+// Modulus, in the same way matrix::dim fixes matrix sizes. A few common
+// moduli are provided; others can be declared the same way:
+//
+//     pub struct P101;
+//     impl Modulus for P101 { fn modulus(_: Option<P101>) -> u64 { 101 } }
+//
+// Modular<P> is a Field when p is prime, so the generic algorithms
+// (algorithms::row_reduce, rank, nullspace, gauss_jordan_inverse and
+// determinant) work over GF(p) unchanged. p must be below 2^32 so that
+// products fit in a u64.
+
+use std::fmt;
+use std::ops;
+
+use scalar::{Semiring, Ring, Field};
+
+pub trait Modulus {
+    /// The modulus this type stands for. The argument only names the
+    /// type and is always None.
+    fn modulus(_: Option<Self>) -> u64;
+}
+
+#[inline(always)]
+pub fn modulus_of<P: Modulus>() -> u64 {
+    Modulus::modulus(None::<P>)
+}
+
+macro_rules! moduli(
+    ($($name:ident = $p:expr),+) => (
+        $(
+            #[deriving(Clone, PartialEq, Eq)]
+            pub struct $name;
+            impl Modulus for $name {
+                #[inline(always)]
+                fn modulus(_: Option<$name>) -> u64 { $p }
+            }
+        )+
+    )
+)
+
+moduli!(P2 = 2, P3 = 3, P5 = 5, P7 = 7, P65537 = 65537,
+        P998244353 = 998244353, P1000000007 = 1000000007)
+
+// A residue, always kept in 0 .. p.
+#[deriving(Clone, PartialEq, Eq)]
+pub struct Modular<P> {
+    val: u64
+}
+
+impl<P: Modulus> Modular<P> {
+    /// The residue of n, which may be negative.
+    pub fn new(n: i64) -> Modular<P> {
+        let p = modulus_of::<P>();
+        assert!(p > 0 && p <= 1 << 32);
+        let p = p as i64;
+        Modular { val: ((n % p + p) % p) as u64 }
+    }
+
+    pub fn value(&self) -> u64 { self.val }
+
+    /// self^e by repeated squaring.
+    pub fn pow(&self, e: u64) -> Modular<P> {
+        let mut result: Modular<P> = Semiring::one();
+        let mut base = self.clone();
+        let mut e = e;
+        while e > 0 {
+            if e & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            e >>= 1;
+        }
+        result
+    }
+
+    /// The multiplicative inverse by the extended Euclidean algorithm,
+    /// or None if self and p are not coprime.
+    pub fn inv(&self) -> Option<Modular<P>> {
+        let p = modulus_of::<P>() as i64;
+        let (mut r0, mut r1) = (p, self.val as i64);
+        let (mut t0, mut t1) = (0i64, 1i64);
+        while r1 != 0 {
+            let q = r0 / r1;
+            let (r2, t2) = (r0 - q * r1, t0 - q * t1);
+            r0 = r1;
+            r1 = r2;
+            t0 = t1;
+            t1 = t2;
+        }
+        if r0 == 1 { Some(Modular::new(t0)) } else { None }
+    }
+}
+
+impl<P: Modulus> ops::Add<Modular<P>, Modular<P>> for Modular<P> {
+    #[inline(always)]
+    fn add(&self, rhs: &Modular<P>) -> Modular<P> {
+        Modular { val: (self.val + rhs.val) % modulus_of::<P>() }
+    }
+}
+
+impl<P: Modulus> ops::Sub<Modular<P>, Modular<P>> for Modular<P> {
+    #[inline(always)]
+    fn sub(&self, rhs: &Modular<P>) -> Modular<P> {
+        let p = modulus_of::<P>();
+        Modular { val: (self.val + p - rhs.val) % p }
+    }
+}
+
+impl<P: Modulus> ops::Mul<Modular<P>, Modular<P>> for Modular<P> {
+    #[inline(always)]
+    fn mul(&self, rhs: &Modular<P>) -> Modular<P> {
+        Modular { val: self.val * rhs.val % modulus_of::<P>() }
+    }
+}
+
+impl<P: Modulus> ops::Div<Modular<P>, Modular<P>> for Modular<P> {
+    fn div(&self, rhs: &Modular<P>) -> Modular<P> {
+        match rhs.inv() {
+            Some(r) => *self * r,
+            None => fail!(format!("{} is not invertible modulo {}.",
+                                  rhs.val, modulus_of::<P>()))
+        }
+    }
+}
+
+impl<P: Modulus> ops::Neg<Modular<P>> for Modular<P> {
+    #[inline(always)]
+    fn neg(&self) -> Modular<P> {
+        let p = modulus_of::<P>();
+        Modular { val: (p - self.val) % p }
+    }
+}
+
+impl<P: Modulus + Clone + PartialEq> Semiring for Modular<P> {
+    #[inline(always)]
+    fn zero() -> Modular<P> { Modular { val: 0 } }
+    #[inline(always)]
+    fn one() -> Modular<P> { Modular { val: 1 % modulus_of::<P>() } }
+}
+
+impl<P: Modulus + Clone + PartialEq> Ring for Modular<P> {}
+
+// Only a field when p is prime; division by a non-unit fails.
+impl<P: Modulus + Clone + PartialEq> Field for Modular<P> {}
+
+impl<P> fmt::Show for Modular<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.val)
+    }
+}
@@ -0,0 +1,129 @@
+// Exact rational numbers over BigInt.
+//
+// Rational is a Field, so the generic algorithms run over it without
+// rounding: algorithms::determinant, gauss_jordan_inverse and
+// gauss_jordan_solve give bit-exact results for integer or rational
+// input. The blockwise algorithms::inverse is exact as well, but fails
+// when a leading block is singular.
+
+use std::fmt;
+use std::ops;
+
+use matrix::{BasicMatrix, Create};
+use scalar::{Semiring, Ring, Field};
+use scalar::bigint::{BigInt, gcd};
+
+// Kept in lowest terms with a positive denominator, so that derived
+// equality is value equality.
+#[deriving(Clone, PartialEq, Eq)]
+pub struct Rational {
+    num: BigInt,
+    den: BigInt
+}
+
+impl Rational {
+    /// num / den in lowest terms. Fails if den is zero.
+    pub fn new(num: BigInt, den: BigInt) -> Rational {
+        if den.is_zero() {
+            fail!("Rational with zero denominator.")
+        }
+        let g = gcd(&num, &den);
+        let (num, den) = (num / g, den / g);
+        if den.is_negative() {
+            Rational { num: -num, den: -den }
+        }
+        else {
+            Rational { num: num, den: den }
+        }
+    }
+
+    pub fn from_integer(n: BigInt) -> Rational {
+        Rational { num: n, den: Semiring::one() }
+    }
+
+    pub fn from_i64(n: i64) -> Rational {
+        Rational::from_integer(BigInt::from_i64(n))
+    }
+
+    pub fn numer<'a>(&'a self) -> &'a BigInt { &self.num }
+    pub fn denom<'a>(&'a self) -> &'a BigInt { &self.den }
+
+    pub fn is_integer(&self) -> bool { self.den == Semiring::one() }
+
+    pub fn abs(&self) -> Rational {
+        Rational { num: self.num.abs(), den: self.den.clone() }
+    }
+}
+
+impl ops::Add<Rational, Rational> for Rational {
+    fn add(&self, rhs: &Rational) -> Rational {
+        Rational::new(self.num * rhs.den + rhs.num * self.den, self.den * rhs.den)
+    }
+}
+
+impl ops::Sub<Rational, Rational> for Rational {
+    fn sub(&self, rhs: &Rational) -> Rational {
+        Rational::new(self.num * rhs.den - rhs.num * self.den, self.den * rhs.den)
+    }
+}
+
+impl ops::Mul<Rational, Rational> for Rational {
+    fn mul(&self, rhs: &Rational) -> Rational {
+        Rational::new(self.num * rhs.num, self.den * rhs.den)
+    }
+}
+
+impl ops::Div<Rational, Rational> for Rational {
+    fn div(&self, rhs: &Rational) -> Rational {
+        if rhs.num.is_zero() {
+            fail!("Rational division by zero.")
+        }
+        Rational::new(self.num * rhs.den, self.den * rhs.num)
+    }
+}
+
+impl ops::Neg<Rational> for Rational {
+    fn neg(&self) -> Rational {
+        Rational { num: -self.num, den: self.den.clone() }
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// The denominators are positive, so cross multiplying keeps the order.
+impl Ord for Rational {
+    fn cmp(&self, other: &Rational) -> Ordering {
+        (self.num * other.den).cmp(&(other.num * self.den))
+    }
+}
+
+impl Semiring for Rational {
+    fn zero() -> Rational { Rational::from_integer(Semiring::zero()) }
+    fn one() -> Rational { Rational::from_integer(Semiring::one()) }
+}
+
+impl Ring for Rational {}
+
+impl Field for Rational {}
+
+impl fmt::Show for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.num)
+        }
+        else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+// Converts a matrix of machine integers to an exact rational one.
+pub fn from_integer_matrix<M: BasicMatrix<i64>, R: BasicMatrix<Rational> + Create<Rational>>(A: &M) -> R {
+    Create::<Rational>::create(A.num_rows(), A.num_cols(), |i, j| {
+        Rational::from_i64(A.get(i, j))
+    })
+}
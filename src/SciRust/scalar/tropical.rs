@@ -0,0 +1,49 @@
+// Tropical semirings, where addition takes the minimum or maximum and
+// multiplication is ordinary addition.
+//
+// A matrix product over MinPlus is one round of shortest path
+// relaxation, (A B)(i, j) = min_k A(i, k) + B(k, j), so with edge
+// weights as entries, A^k holds the shortest walks of k edges and the
+// closure in matrix::algorithms holds all shortest paths. MaxPlus does
+// the same for longest paths, as in critical path scheduling.
+//
+// The semiring zero is a missing edge (infinity for MinPlus, minus
+// infinity for MaxPlus) and the semiring one is the empty path, 0.
+
+use std::num;
+use std::ops;
+
+use scalar::Semiring;
+
+macro_rules! tropical(
+    ($name:ident, $op:ident, $zero:ident) => (
+        #[deriving(Clone, PartialEq, PartialOrd, Show)]
+        pub struct $name<T>(pub T);
+
+        impl<T: Float> ops::Add<$name<T>, $name<T>> for $name<T> {
+            #[inline(always)]
+            fn add(&self, rhs: &$name<T>) -> $name<T> {
+                let (&$name(a), &$name(b)) = (self, rhs);
+                $name(a.$op(b))
+            }
+        }
+
+        impl<T: Float> ops::Mul<$name<T>, $name<T>> for $name<T> {
+            #[inline(always)]
+            fn mul(&self, rhs: &$name<T>) -> $name<T> {
+                let (&$name(a), &$name(b)) = (self, rhs);
+                $name(a + b)
+            }
+        }
+
+        impl<T: Float> Semiring for $name<T> {
+            #[inline(always)]
+            fn zero() -> $name<T> { $name(Float::$zero()) }
+            #[inline(always)]
+            fn one() -> $name<T> { $name(num::zero()) }
+        }
+    )
+)
+
+tropical!(MinPlus, min, infinity)
+tropical!(MaxPlus, max, neg_infinity)
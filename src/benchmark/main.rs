@@ -8,11 +8,12 @@ extern crate SciRust = "SciRust#0.3pre";
 
 use time::precise_time_s;
 
-use SciRust::matrix::{Matrix, TransposeMatrix, transpose};
-use SciRust::matrix::generate::{rand_L1};
+use SciRust::matrix::{Matrix, TransposeMatrix, transpose, Lower};
+use SciRust::matrix::generate::{rand_L1, zero_matrix};
+use SciRust::matrix::triangular::LowerTriangular;
 use SciRust::matrix::algorithms::{mat_mul, cholesky_seq_inplace,
                         inverse, cholesky_blocked, mat_mul_blocked,
-                        convert};
+                        convert, syrk, NoTrans};
 //use SciRust::matrix::par;
 
 type M = Matrix<f64>;
@@ -37,6 +38,13 @@ fn benchmark(N: uint) {
     
     println!("Matrix Multiply (blocked): {:?}s", stop - start);
 
+    let mut _As: M = zero_matrix(N, N);
+    let start = precise_time_s();
+    syrk(Lower, NoTrans, 1.0, &L, 0.0, &mut _As);
+    let stop = precise_time_s();
+
+    println!("Symmetric Rank-k Update: {:?}s", stop - start);
+
     //let Ls: M = convert(&L);
     //let Ls = Arc::new(Ls);
     //let Lts: M = convert(&Lt);
@@ -62,13 +70,13 @@ fn benchmark(N: uint) {
 
     let mut A2 = convert(&A);
     let start = precise_time_s();
-    cholesky_seq_inplace::<M>(&mut A2);
+    cholesky_seq_inplace(&mut A2);
     let stop = precise_time_s();
 
     println!("Cholesky (sequential): {:?}s", stop - start);
 
     let start = precise_time_s();
-    let _Ac: M = cholesky_blocked(&A);
+    let _Ac: LowerTriangular<f64> = cholesky_blocked(&A);
     let stop = precise_time_s();
 
     println!("Cholesky (blocked): {:?}s", stop - start);   